@@ -0,0 +1,235 @@
+//! An async client for Tokio based bridges, enabled with the `tokio` feature.
+//!
+//! Frames are encoded exactly like [`send_message`] does, so projectFly can't tell the difference.
+//!
+//! ```
+//! use pfly_rust::async_client::AsyncPflyConnection;
+//! use pfly_rust::mock::MockServer;
+//! use pfly_rust::PflyIpcData;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let server = MockServer::start()?;
+//! # let builder = server.connection_builder();
+//! let mut connection = AsyncPflyConnection::connect_with(&builder).await?;
+//!
+//! connection.send(&PflyIpcData::builder().aircraft_type("B77W").build()).await?;
+//! # assert!(server.recv_timeout(std::time::Duration::from_secs(1)).is_some());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`send_message`]: crate::send_message
+
+use crate::connection::Target;
+use crate::landing::Landing;
+use crate::pipeline::Pipeline;
+use crate::record::Recorder;
+use crate::relay::Signer;
+use crate::throttle::ChangeThresholds;
+use crate::transport::check_datagram;
+use crate::{PflyConnection, PflyConnectionBuilder, PflyError, PflyIpcData, Result};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket, UnixStream};
+
+/// The async counterpart of [`PflyConnection`], backed by a [`tokio::net::UnixStream`],
+/// or a TCP stream or UDP socket when the builder was told to use [`tcp`] or [`udp`].
+///
+/// [`PflyConnection`]: crate::PflyConnection
+/// [`tcp`]: crate::PflyConnectionBuilder::tcp
+/// [`udp`]: crate::PflyConnectionBuilder::udp
+#[derive(Debug)]
+pub struct AsyncPflyConnection {
+    stream: Stream,
+    signer: Option<Signer>,
+    pipeline: Pipeline,
+}
+
+impl AsyncPflyConnection {
+    /// Connects to `$PFLY_SOCKET`, or `/tmp/pf.sock` if that isn't set.
+    pub async fn connect() -> Result<AsyncPflyConnection> {
+        AsyncPflyConnection::connect_with(&PflyConnection::builder()).await
+    }
+
+    /// Connects using the path or network address, relay key, connect timeout, strict mode,
+    /// recorder, landing detection and rate limit of `builder`.
+    ///
+    /// The non-blocking option doesn't apply here, Tokio streams never block.
+    pub async fn connect_with(builder: &PflyConnectionBuilder) -> Result<AsyncPflyConnection> {
+        builder.check()?;
+
+        let stream = match &builder.target {
+            Target::Unix(_) => {
+                Stream::Unix(timeout(builder, UnixStream::connect(builder.socket_path())).await?)
+            }
+            Target::Tcp(address) => {
+                let stream = timeout(builder, TcpStream::connect(address.as_str())).await?;
+                stream.set_nodelay(true).map_err(PflyError::Socket)?;
+                Stream::Tcp(stream)
+            }
+            Target::Udp(address) => Stream::Udp(connect_udp(address).await?),
+        };
+
+        Ok(AsyncPflyConnection {
+            stream,
+            signer: builder.relay_key.clone().map(Signer::new),
+            pipeline: Pipeline::from_builder(builder),
+        })
+    }
+
+    /// Wraps a stream that is already connected to projectFly.
+    pub fn new(stream: UnixStream) -> AsyncPflyConnection {
+        AsyncPflyConnection::from_stream(Stream::Unix(stream))
+    }
+
+    /// Wraps a TCP stream that is already connected to something forwarding to projectFly.
+    pub fn new_tcp(stream: TcpStream) -> AsyncPflyConnection {
+        AsyncPflyConnection::from_stream(Stream::Tcp(stream))
+    }
+
+    fn from_stream(stream: Stream) -> AsyncPflyConnection {
+        AsyncPflyConnection {
+            stream,
+            signer: None,
+            pipeline: Pipeline::default(),
+        }
+    }
+
+    /// Sends a single frame, leaving the connection open for the next one.
+    ///
+    /// Frames held back by the rate limit return `Ok` without sending anything,
+    /// like [`PflyConnection::send`].
+    ///
+    /// [`PflyConnection::send`]: crate::PflyConnection::send
+    pub async fn send(&mut self, data: &PflyIpcData) -> Result<()> {
+        match self.pipeline.prepare(data)? {
+            Some((data, now)) => self.write(&data, now).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the latest frame the rate limit held back, if there is one.
+    pub async fn flush(&mut self) -> Result<()> {
+        match self.pipeline.take_pending() {
+            Some(data) => self.write(&data, Instant::now()).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn write(&mut self, data: &PflyIpcData, now: Instant) -> Result<()> {
+        let mut payload = crate::wire::encode(data);
+        if let Some(signer) = self.signer.as_mut() {
+            payload = signer.seal(&payload);
+        }
+
+        match &mut self.stream {
+            Stream::Unix(stream) => stream.write_all(&payload).await.map_err(PflyError::Write)?,
+            Stream::Tcp(stream) => stream.write_all(&payload).await.map_err(PflyError::Write)?,
+            Stream::Udp(socket) => {
+                let sent = socket.send(&payload).await.map_err(PflyError::Write)?;
+                check_datagram(sent, &payload)?;
+            }
+        }
+
+        self.pipeline.sent(data, now)
+    }
+
+    /// Turns strict mode on or off, see [`PflyConnectionBuilder::strict`].
+    ///
+    /// [`PflyConnectionBuilder::strict`]: crate::PflyConnectionBuilder::strict
+    pub fn set_strict(&mut self, strict: bool) {
+        self.pipeline.set_strict(strict);
+    }
+
+    /// Starts or stops recording sent frames, see [`PflyConnectionBuilder::recorder`].
+    ///
+    /// [`PflyConnectionBuilder::recorder`]: crate::PflyConnectionBuilder::recorder
+    pub fn set_recorder(&mut self, recorder: Option<Arc<Mutex<Recorder>>>) {
+        self.pipeline.set_recorder(recorder);
+    }
+
+    /// Turns landing detection on or off, see [`PflyConnectionBuilder::detect_landings`].
+    ///
+    /// [`PflyConnectionBuilder::detect_landings`]: crate::PflyConnectionBuilder::detect_landings
+    pub fn set_detect_landings(&mut self, detect_landings: bool) {
+        self.pipeline.set_detect_landings(detect_landings);
+    }
+
+    /// Changes the rate limit, see [`PflyConnection::set_rate_limit`].
+    ///
+    /// [`PflyConnection::set_rate_limit`]: crate::PflyConnection::set_rate_limit
+    pub fn set_rate_limit(&mut self, max_rate: Option<f64>, thresholds: Option<ChangeThresholds>) {
+        self.pipeline.set_rate_limit(max_rate, thresholds);
+    }
+
+    /// The last landing seen on this connection, with landing detection on.
+    pub fn landing(&self) -> Option<Landing> {
+        self.pipeline.landing()
+    }
+
+    /// Gives back the underlying stream, `None` if it is a TCP stream or UDP socket.
+    pub fn into_stream(self) -> Option<UnixStream> {
+        match self.stream {
+            Stream::Unix(stream) => Some(stream),
+            Stream::Tcp(_) | Stream::Udp(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Stream {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+/// Like [`UdpTransport::connect`], on Tokio.
+///
+/// [`UdpTransport::connect`]: crate::transport::UdpTransport::connect
+async fn connect_udp(address: &str) -> Result<UdpSocket> {
+    let addr = tokio::net::lookup_host(address)
+        .await
+        .map_err(PflyError::Address)?
+        .next()
+        .ok_or_else(|| {
+            PflyError::Address(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "address did not resolve to anything",
+            ))
+        })?;
+    let local: SocketAddr = if addr.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+
+    let socket = UdpSocket::bind(local).await.map_err(PflyError::Socket)?;
+    socket
+        .connect(addr)
+        .await
+        .map_err(PflyError::from_connect)?;
+
+    Ok(socket)
+}
+
+/// Waits for `connect`, up to the builder's connect timeout if it has one.
+async fn timeout<T, F>(builder: &PflyConnectionBuilder, connect: F) -> Result<T>
+where
+    F: std::future::Future<Output = io::Result<T>>,
+{
+    match builder.connect_timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, connect).await {
+            Ok(stream) => stream,
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "connecting to projectFly timed out",
+            )),
+        },
+        None => connect.await,
+    }
+    .map_err(PflyError::from_connect)
+}
@@ -0,0 +1,328 @@
+//! Reads a simulator and forwards its telemetry to projectFly at a fixed rate.
+//!
+//! Usage: `pfly-bridge [CONFIG]`, where `CONFIG` defaults to `pfly-bridge.toml`.
+//! See `pfly-bridge.example.toml` for the available settings, logging is controlled with `RUST_LOG`.
+
+use log::{debug, error, info, warn};
+use pfly_rust::landing::LandingDetector;
+use pfly_rust::phase::PhaseDetector;
+use pfly_rust::record::Recorder;
+use pfly_rust::sources::flightgear::FlightGearSource;
+use pfly_rust::sources::xplane::data::{DataListener, DataMapping};
+use pfly_rust::sources::xplane::RrefSource;
+use pfly_rust::sources::Source;
+use pfly_rust::{PflyConnection, PflyError, PflyIpcData, ReconnectingConnection};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a source may block before checking for shutdown.
+const SOURCE_POLL: Duration = Duration::from_millis(500);
+
+/// How often a quiet source is reset, e.g. to subscribe again once X-Plane is back.
+const SOURCE_RESET: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// Frames per second sent to projectFly.
+    #[serde(default = "default_rate")]
+    rate: f64,
+    /// projectFly socket, defaults to `$PFLY_SOCKET` or `/tmp/pf.sock`.
+    socket: Option<PathBuf>,
+    /// Send over TCP to this address instead, for projectFly on another machine.
+    tcp: Option<String>,
+    /// Or over UDP, to a `pfly-relay`.
+    udp: Option<String>,
+    /// Key shared with the `pfly-relay` at `tcp` or `udp`.
+    relay_key: Option<String>,
+    /// Refuse to send frames that fail validation.
+    #[serde(default)]
+    strict: bool,
+    /// Work out the touchdown rate and G load here, none of the sources report them.
+    #[serde(default = "default_detect_landings")]
+    detect_landings: bool,
+    source: SourceConfig,
+    /// Keep a recording of everything sent.
+    record: Option<RecordConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+enum SourceConfig {
+    XplaneRref {
+        #[serde(default = "default_xplane_address")]
+        address: String,
+        #[serde(default = "default_rref_frequency")]
+        frequency: u32,
+    },
+    XplaneData {
+        address: String,
+        aircraft_type: Option<String>,
+    },
+    Flightgear {
+        address: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordConfig {
+    dir: PathBuf,
+    max_file_size: Option<u64>,
+    max_files: Option<usize>,
+}
+
+impl RecordConfig {
+    fn open(&self) -> pfly_rust::Result<Recorder> {
+        let mut recorder = Recorder::create(&self.dir)?;
+        if let Some(max_file_size) = self.max_file_size {
+            recorder = recorder.max_file_size(max_file_size);
+        }
+        if let Some(max_files) = self.max_files {
+            recorder = recorder.max_files(max_files);
+        }
+        Ok(recorder)
+    }
+}
+
+fn default_rate() -> f64 {
+    10.0
+}
+
+fn default_detect_landings() -> bool {
+    true
+}
+
+fn default_xplane_address() -> String {
+    format!("127.0.0.1:{}", RrefSource::DEFAULT_PORT)
+}
+
+fn default_rref_frequency() -> u32 {
+    20
+}
+
+impl SourceConfig {
+    fn open(&self) -> pfly_rust::Result<Box<dyn Source + Send>> {
+        Ok(match self {
+            SourceConfig::XplaneRref { address, frequency } => {
+                let source = RrefSource::connect(address.as_str(), *frequency)?;
+                source.set_read_timeout(Some(SOURCE_POLL))?;
+                Box::new(source)
+            }
+            SourceConfig::XplaneData {
+                address,
+                aircraft_type,
+            } => {
+                let mut source = DataListener::bind(address.as_str(), DataMapping::default())?;
+                if let Some(aircraft_type) = aircraft_type {
+                    source = source.aircraft_type(aircraft_type.clone());
+                }
+                source.set_read_timeout(Some(SOURCE_POLL))?;
+                Box::new(source)
+            }
+            SourceConfig::Flightgear { address } => {
+                let source = FlightGearSource::bind(address.as_str())?;
+                source.set_read_timeout(Some(SOURCE_POLL))?;
+                Box::new(source)
+            }
+        })
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let config_path = std::env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("pfly-bridge.toml"));
+
+    let config = match load_config(&config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{}: {}", config_path.display(), err);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(config) {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn load_config(path: &Path) -> Result<Config, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let config: Config = toml::from_str(&text).map_err(|err| err.to_string())?;
+
+    if !(config.rate > 0.0 && Duration::try_from_secs_f64(config.rate.recip()).is_ok()) {
+        return Err(format!(
+            "rate must be above 0 and not vanishingly small, got {}",
+            config.rate
+        ));
+    }
+    let targets = [
+        config.socket.is_some(),
+        config.tcp.is_some(),
+        config.udp.is_some(),
+    ];
+    if targets.iter().filter(|set| **set).count() > 1 {
+        return Err("only one of socket, tcp and udp can be set".to_owned());
+    }
+    if config.relay_key.is_some() && config.tcp.is_none() && config.udp.is_none() {
+        return Err("relay_key needs tcp or udp".to_owned());
+    }
+    if config.relay_key.as_deref() == Some("") {
+        return Err("relay_key must not be empty".to_owned());
+    }
+
+    Ok(config)
+}
+
+fn run(config: Config) -> pfly_rust::Result<()> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = Arc::clone(&shutdown);
+        ctrlc::set_handler(move || shutdown.store(true, Ordering::SeqCst))
+            .expect("could not install signal handler");
+    }
+
+    let source = config.source.open()?;
+    info!("reading {:?}", config.source);
+
+    let latest: Arc<Mutex<Option<PflyIpcData>>> = Arc::new(Mutex::new(None));
+    let reader = {
+        let latest = Arc::clone(&latest);
+        let shutdown = Arc::clone(&shutdown);
+        let landings = if config.detect_landings {
+            Some(LandingDetector::new())
+        } else {
+            None
+        };
+        thread::spawn(move || read_source(source, landings, latest, shutdown))
+    };
+
+    let mut builder = PflyConnection::builder().strict(config.strict);
+    if let Some(socket) = &config.socket {
+        builder = builder.path(socket);
+    }
+    if let Some(address) = &config.tcp {
+        builder = builder.tcp(address.as_str());
+    }
+    if let Some(address) = &config.udp {
+        builder = builder.udp(address.as_str());
+    }
+    if let Some(key) = &config.relay_key {
+        builder = builder.relay_key(key);
+    }
+    if let Some(record) = &config.record {
+        let recorder = record.open()?;
+        info!("recording to {}", recorder.path().display());
+        builder = builder.recorder(recorder);
+    }
+    let target = match (builder.tcp_address(), builder.udp_address()) {
+        (Some(address), _) => format!("tcp://{}", address),
+        (_, Some(address)) => format!("udp://{}", address),
+        _ => builder.socket_path().display().to_string(),
+    };
+    info!("forwarding to {} at {} Hz", target, config.rate);
+
+    let mut connection = ReconnectingConnection::new(builder)
+        .buffer_latest(true)
+        .on_state_change(|state| info!("projectFly connection: {:?}", state));
+
+    let mut phases = PhaseDetector::new();
+
+    let interval = Duration::from_secs_f64(1.0 / config.rate);
+    let mut next_tick = Instant::now();
+
+    while !shutdown.load(Ordering::SeqCst) {
+        let frame = latest.lock().unwrap().clone();
+
+        match frame {
+            Some(frame) => {
+                if let Some(change) = phases.update(&frame) {
+                    info!("phase: {}", change.to);
+                }
+
+                match connection.send(&frame) {
+                    Ok(()) => {}
+                    Err(PflyError::Disconnected) => {}
+                    Err(err) if err.is_disconnect() => debug!("{}", err),
+                    Err(err) => warn!("could not send frame: {}", err),
+                }
+            }
+            None => debug!("no frame from the simulator yet"),
+        }
+
+        next_tick += interval;
+        let now = Instant::now();
+        if next_tick > now {
+            thread::sleep(next_tick - now);
+        } else {
+            // Fell behind, don't try to catch up with a burst of frames.
+            next_tick = now;
+        }
+    }
+
+    info!("shutting down");
+    let _ = reader.join();
+
+    Ok(())
+}
+
+fn read_source(
+    mut source: Box<dyn Source + Send>,
+    mut landings: Option<LandingDetector>,
+    latest: Arc<Mutex<Option<PflyIpcData>>>,
+    shutdown: Arc<AtomicBool>,
+) {
+    let mut receiving = false;
+    let mut last_reset = Instant::now();
+
+    while !shutdown.load(Ordering::SeqCst) {
+        match source.recv_frame() {
+            Ok(mut frame) => {
+                // Every frame, not just the ones forwarded, so the touchdown isn't missed.
+                if let Some(landings) = landings.as_mut() {
+                    if let Some(landing) = landings.apply(&mut frame) {
+                        info!(
+                            "touchdown at {} fpm, {:.2} G, {} bounces",
+                            landing.vertical_speed,
+                            f64::from(landing.g_force) / 1000.0,
+                            landing.bounces
+                        );
+                    }
+                }
+
+                if !receiving {
+                    info!("receiving simulator data");
+                    receiving = true;
+                }
+                *latest.lock().unwrap() = Some(frame);
+            }
+            Err(PflyError::Source(err))
+                if err.kind() == std::io::ErrorKind::WouldBlock
+                    || err.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                if receiving || last_reset.elapsed() >= SOURCE_RESET {
+                    if receiving {
+                        warn!("simulator stopped sending data");
+                        *latest.lock().unwrap() = None;
+                        receiving = false;
+                    }
+                    // The simulator may have restarted or not be running yet.
+                    if let Err(err) = source.reset() {
+                        warn!("could not reset the source: {}", err);
+                    }
+                    last_reset = Instant::now();
+                }
+            }
+            Err(err) => warn!("{}", err),
+        }
+    }
+}
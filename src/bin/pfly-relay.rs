@@ -0,0 +1,532 @@
+//! Accepts signed frames from bridges on other machines and forwards them to projectFly.
+//!
+//! Usage: `pfly-relay [CONFIG]`, where `CONFIG` defaults to `pfly-relay.toml`.
+//! See `pfly-relay.example.toml` for the available settings, logging is controlled with `RUST_LOG`.
+//!
+//! Bridges send to the relay with `PflyConnectionBuilder::relay_key` and the same key, the
+//! format is described in the `relay` module.
+
+use log::{debug, error, info, warn};
+use pfly_rust::relay::{self, Rejection, Verifier};
+use pfly_rust::{PflyConnection, PflyIpcData, ReconnectingConnection};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the forwarding loop checks for shutdown and due statistics.
+const POLL: Duration = Duration::from_millis(500);
+
+/// Keys shorter than this are easy to guess.
+const MIN_KEY_LEN: usize = 16;
+
+/// Addresses with statistics of their own, later ones are lumped together.
+const MAX_SOURCES: usize = 256;
+
+/// TCP connections open at the same time, further ones are turned away.
+const MAX_CONNECTIONS: usize = 64;
+
+/// How long a TCP connection may go without sending before it is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pause after a failed accept or receive, so an error that keeps coming back doesn't spin.
+const ERROR_PAUSE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// Shared with every bridge sending here.
+    key: String,
+    /// Address to accept TCP connections on.
+    tcp: Option<String>,
+    /// Address to receive UDP datagrams on.
+    udp: Option<String>,
+    /// projectFly socket, defaults to `$PFLY_SOCKET` or `/tmp/pf.sock`.
+    socket: Option<PathBuf>,
+    /// Addresses or networks bridges may send from, anyone with the key if empty.
+    #[serde(default)]
+    allow: Vec<String>,
+    /// Seconds the clocks of bridge and relay may be apart.
+    #[serde(default = "default_max_clock_skew")]
+    max_clock_skew: f64,
+    /// Seconds between statistics in the log, 0 only logs them on exit.
+    #[serde(default = "default_stats_interval")]
+    stats_interval: f64,
+}
+
+fn default_max_clock_skew() -> f64 {
+    30.0
+}
+
+fn default_stats_interval() -> f64 {
+    60.0
+}
+
+/// An allowlist entry, a single address or a network like `192.168.1.0/24`.
+#[derive(Debug, Clone, Copy)]
+struct Network {
+    addr: IpAddr,
+    prefix: u32,
+}
+
+impl Network {
+    fn parse(network: &str) -> Result<Network, String> {
+        let invalid = || format!("invalid address or network {:?} in allow", network);
+
+        let (addr, prefix) = match network.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (network, None),
+        };
+        let addr: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let bits = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse().map_err(|_| invalid())?,
+            None => bits,
+        };
+        if prefix > bits {
+            return Err(invalid());
+        }
+
+        Ok(Network { addr, prefix })
+    }
+
+    fn contains(&self, addr: IpAddr) -> bool {
+        // Bridges on an IPv4 network may show up as mapped addresses on a dual stack socket.
+        let addr = match addr {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+            IpAddr::V4(_) => addr,
+        };
+
+        match (self.addr, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                masked(u32::from(network).into(), 32, self.prefix)
+                    == masked(u32::from(addr).into(), 32, self.prefix)
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                masked(network.into(), 128, self.prefix) == masked(addr.into(), 128, self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Keeps the first `prefix` of `bits` bits.
+fn masked(addr: u128, bits: u32, prefix: u32) -> u128 {
+    match bits - prefix {
+        0 => addr,
+        128 => 0,
+        host => addr >> host << host,
+    }
+}
+
+/// What happened with the envelopes from one address, or from all the untracked ones.
+#[derive(Debug, Default)]
+struct Stats {
+    accepted: u64,
+    forwarded: u64,
+    bytes: u64,
+    expired: u64,
+    replayed: u64,
+    last_seen: Option<Instant>,
+    /// `accepted` at the last report, for the rate since then.
+    reported: u64,
+}
+
+/// Statistics for the whole relay.
+///
+/// Only senders that have the key get an entry of their own, the rest are just counted, as
+/// UDP source addresses are easy to make up.
+#[derive(Debug, Default)]
+struct Totals {
+    sources: HashMap<IpAddr, Stats>,
+    /// Signed envelopes from more addresses than `MAX_SOURCES`.
+    untracked: Stats,
+    denied: u64,
+    unauthenticated: u64,
+    malformed: u64,
+}
+
+impl Totals {
+    fn source(&mut self, source: IpAddr) -> &mut Stats {
+        if self.sources.len() >= MAX_SOURCES && !self.sources.contains_key(&source) {
+            return &mut self.untracked;
+        }
+
+        self.sources.entry(source).or_default()
+    }
+}
+
+/// Everything the listener threads share.
+struct Relay {
+    allow: Vec<Network>,
+    verifier: Mutex<Verifier>,
+    stats: Mutex<Totals>,
+    frames: Mutex<Sender<(IpAddr, PflyIpcData)>>,
+    /// TCP connections currently open.
+    connections: AtomicUsize,
+}
+
+impl Relay {
+    /// Whether `source` may send here at all, counting it if not.
+    fn allowed(&self, source: IpAddr) -> bool {
+        if self.allow.is_empty() || self.allow.iter().any(|network| network.contains(source)) {
+            return true;
+        }
+
+        let mut stats = self.stats.lock().unwrap();
+        if stats.denied == 0 {
+            warn!("{}: not in allow, ignoring it and others like it", source);
+        } else {
+            debug!("{}: not in allow", source);
+        }
+        stats.denied += 1;
+
+        false
+    }
+
+    /// Checks an envelope from `source` and queues the frame for projectFly.
+    fn receive(&self, source: IpAddr, envelope: &[u8]) -> Result<(), Rejection> {
+        let result = self.verifier.lock().unwrap().verify(source, envelope);
+        self.count(
+            source,
+            envelope.len(),
+            result.as_ref().map(drop).map_err(|r| *r),
+        );
+
+        let envelope = result?;
+        let _ = self.frames.lock().unwrap().send((source, envelope.frame));
+        Ok(())
+    }
+
+    /// Adds an envelope of `len` bytes from `source` to the statistics.
+    fn count(&self, source: IpAddr, len: usize, result: Result<(), Rejection>) {
+        let mut totals = self.stats.lock().unwrap();
+
+        let counter = match result {
+            // Anyone can send these, from any address.
+            Err(Rejection::Unauthenticated) => &mut totals.unauthenticated,
+            Err(Rejection::Malformed) => &mut totals.malformed,
+            _ => {
+                let stats = totals.source(source);
+                stats.last_seen = Some(Instant::now());
+                stats.bytes += len as u64;
+
+                match result {
+                    Ok(()) => &mut stats.accepted,
+                    Err(Rejection::Expired) => &mut stats.expired,
+                    _ => &mut stats.replayed,
+                }
+            }
+        };
+
+        match result {
+            Ok(()) if *counter == 0 => info!("{}: receiving frames", source),
+            Err(rejection) if *counter == 0 => {
+                warn!("{}: rejected {} envelope", source, rejection)
+            }
+            Err(rejection) => debug!("{}: rejected {} envelope", source, rejection),
+            Ok(()) => {}
+        }
+        *counter += 1;
+    }
+
+    fn forwarded(&self, source: IpAddr) {
+        self.stats.lock().unwrap().source(source).forwarded += 1;
+    }
+
+    fn log_stats(&self, interval: Option<Duration>) {
+        let mut totals = self.stats.lock().unwrap();
+        info!(
+            "stats: {} denied, {} unauthenticated, {} malformed",
+            totals.denied, totals.unauthenticated, totals.malformed
+        );
+
+        let Totals {
+            sources, untracked, ..
+        } = &mut *totals;
+        let mut sources: Vec<_> = sources
+            .iter_mut()
+            .map(|(source, stats)| (source.to_string(), stats))
+            .collect();
+        sources.sort_by(|a, b| a.0.cmp(&b.0));
+        if untracked.last_seen.is_some() {
+            sources.push(("other addresses".to_owned(), untracked));
+        }
+        if sources.is_empty() {
+            info!("stats: no signed frames received yet");
+        }
+
+        for (source, stats) in sources {
+            let rate = match interval {
+                Some(interval) => format!(
+                    " ({:.1}/s)",
+                    (stats.accepted - stats.reported) as f64 / interval.as_secs_f64()
+                ),
+                None => String::new(),
+            };
+            stats.reported = stats.accepted;
+
+            let last_seen = stats.last_seen.map_or_else(
+                || "never".to_owned(),
+                |at| format!("{:.1}s ago", at.elapsed().as_secs_f64()),
+            );
+
+            info!(
+                "stats: {}: {} accepted{}, {} forwarded, {} bytes, {} expired, {} replayed, last seen {}",
+                source,
+                stats.accepted,
+                rate,
+                stats.forwarded,
+                stats.bytes,
+                stats.expired,
+                stats.replayed,
+                last_seen
+            );
+        }
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let path = std::env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("pfly-relay.toml"));
+
+    let config = match load_config(&path) {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{}: {}", path.display(), err);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(config) {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn load_config(path: &Path) -> Result<(Config, Vec<Network>), String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let config: Config = toml::from_str(&text).map_err(|err| err.to_string())?;
+
+    if config.key.is_empty() {
+        return Err("key must not be empty".to_owned());
+    }
+    if config.key.len() < MIN_KEY_LEN {
+        warn!(
+            "key is shorter than {} characters, anyone on the network might guess it",
+            MIN_KEY_LEN
+        );
+    }
+    if config.tcp.is_none() && config.udp.is_none() {
+        return Err("set tcp, udp or both to receive anything".to_owned());
+    }
+    if !(config.max_clock_skew > 0.0 && Duration::try_from_secs_f64(config.max_clock_skew).is_ok())
+    {
+        return Err(format!(
+            "max_clock_skew must be above 0 seconds and not absurdly large, got {}",
+            config.max_clock_skew
+        ));
+    }
+    if Duration::try_from_secs_f64(config.stats_interval).is_err() {
+        return Err(format!(
+            "stats_interval must be 0 or more seconds and not absurdly large, got {}",
+            config.stats_interval
+        ));
+    }
+
+    let allow = config
+        .allow
+        .iter()
+        .map(|network| Network::parse(network))
+        .collect::<Result<_, _>>()?;
+
+    Ok((config, allow))
+}
+
+fn run((config, allow): (Config, Vec<Network>)) -> pfly_rust::Result<()> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = Arc::clone(&shutdown);
+        ctrlc::set_handler(move || shutdown.store(true, Ordering::SeqCst))
+            .expect("could not install signal handler");
+    }
+
+    if allow.is_empty() {
+        warn!("allow is empty, accepting frames from anyone who has the key");
+    }
+
+    let (sender, frames) = mpsc::channel();
+    let relay = Arc::new(Relay {
+        allow,
+        verifier: Mutex::new(
+            Verifier::new(&config.key).max_skew(Duration::from_secs_f64(config.max_clock_skew)),
+        ),
+        stats: Mutex::new(Totals::default()),
+        frames: Mutex::new(sender),
+        connections: AtomicUsize::new(0),
+    });
+
+    if let Some(address) = &config.tcp {
+        let listener = TcpListener::bind(address.as_str()).map_err(pfly_rust::PflyError::Socket)?;
+        info!("accepting TCP connections on {}", address);
+        let relay = Arc::clone(&relay);
+        thread::spawn(move || accept_tcp(listener, relay));
+    }
+    if let Some(address) = &config.udp {
+        let socket = UdpSocket::bind(address.as_str()).map_err(pfly_rust::PflyError::Socket)?;
+        info!("receiving UDP datagrams on {}", address);
+        let relay = Arc::clone(&relay);
+        thread::spawn(move || receive_udp(socket, relay));
+    }
+
+    let mut builder = PflyConnection::builder();
+    if let Some(socket) = &config.socket {
+        builder = builder.path(socket);
+    }
+    info!("forwarding to {}", builder.socket_path().display());
+
+    let mut connection = ReconnectingConnection::new(builder)
+        .buffer_latest(true)
+        .on_state_change(|state| info!("projectFly connection: {:?}", state));
+
+    let stats_interval =
+        Some(Duration::from_secs_f64(config.stats_interval)).filter(|interval| !interval.is_zero());
+    let mut next_stats = stats_interval.and_then(|interval| Instant::now().checked_add(interval));
+    let mut current = None;
+
+    while !shutdown.load(Ordering::SeqCst) {
+        match frames.recv_timeout(POLL) {
+            Ok((source, frame)) => {
+                if current.replace(source) != Some(source) {
+                    info!("forwarding frames from {}", source);
+                }
+
+                match connection.send(&frame) {
+                    Ok(()) => relay.forwarded(source),
+                    Err(err) if err.is_disconnect() => debug!("dropped frame: {}", err),
+                    Err(err) => warn!("could not forward frame from {}: {}", source, err),
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if let (Some(interval), Some(due)) = (stats_interval, next_stats) {
+            if Instant::now() >= due {
+                relay.log_stats(Some(interval));
+                next_stats = due.checked_add(interval);
+            }
+        }
+    }
+
+    info!("shutting down");
+    relay.log_stats(None);
+    Ok(())
+}
+
+fn accept_tcp(listener: TcpListener, relay: Arc<Relay>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("could not accept connection: {}", err);
+                thread::sleep(ERROR_PAUSE);
+                continue;
+            }
+        };
+        let source = match stream.peer_addr() {
+            Ok(addr) => addr.ip(),
+            Err(_) => continue,
+        };
+
+        if !relay.allowed(source) {
+            continue;
+        }
+        if relay.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            relay.connections.fetch_sub(1, Ordering::SeqCst);
+            warn!(
+                "{}: already {} connections open, hanging up",
+                source, MAX_CONNECTIONS
+            );
+            continue;
+        }
+        if let Err(err) = stream.set_read_timeout(Some(IDLE_TIMEOUT)) {
+            relay.connections.fetch_sub(1, Ordering::SeqCst);
+            warn!("{}: could not set a read timeout: {}", source, err);
+            continue;
+        }
+
+        debug!("{}: connected", source);
+        let relay = Arc::clone(&relay);
+        thread::spawn(move || {
+            receive_tcp(stream, source, &relay);
+            relay.connections.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Reads envelopes until the bridge hangs up, goes quiet or sends something that isn't one.
+fn receive_tcp(stream: TcpStream, source: IpAddr, relay: &Relay) {
+    loop {
+        let envelope = match relay::read_envelope(&stream) {
+            Ok(envelope) => envelope,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                // There is no telling where the next envelope starts.
+                relay.count(source, 0, Err(Rejection::Malformed));
+                break;
+            }
+            Err(err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::TimedOut =>
+            {
+                debug!(
+                    "{}: nothing sent for {:?}, hanging up",
+                    source, IDLE_TIMEOUT
+                );
+                break;
+            }
+            Err(err) => {
+                debug!("{}: {}", source, err);
+                break;
+            }
+        };
+
+        match relay.receive(source, &envelope) {
+            Ok(()) | Err(Rejection::Expired) | Err(Rejection::Replayed) => {}
+            Err(Rejection::Malformed) | Err(Rejection::Unauthenticated) => break,
+        }
+    }
+
+    debug!("{}: disconnected", source);
+}
+
+fn receive_udp(socket: UdpSocket, relay: Arc<Relay>) {
+    // One more byte than the longest envelope, so one that's too long doesn't fit either.
+    let mut buffer = vec![0; relay::MAX_ENVELOPE_LEN + 1];
+
+    loop {
+        let (len, source) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(err) => {
+                warn!("could not receive datagram: {}", err);
+                thread::sleep(ERROR_PAUSE);
+                continue;
+            }
+        };
+
+        if relay.allowed(source.ip()) {
+            let _ = relay.receive(source.ip(), &buffer[..len]);
+        }
+    }
+}
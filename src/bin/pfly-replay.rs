@@ -0,0 +1,162 @@
+//! Plays a recording made with `record::Recorder` back into projectFly.
+//!
+//! Usage: `pfly-replay [--speed N] [--seek [MM:]SS] [--loop] [--socket PATH] RECORDING`
+//!
+//! While playing, press Enter to pause or resume and type `q` followed by Enter to stop.
+
+use log::{error, info};
+use pfly_rust::replay::{Replay, ReplayControl};
+use pfly_rust::PflyConnection;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+const USAGE: &str =
+    "usage: pfly-replay [--speed N] [--seek [MM:]SS] [--loop] [--socket PATH] RECORDING";
+
+#[derive(Debug)]
+struct Args {
+    recording: PathBuf,
+    speed: f64,
+    seek: Duration,
+    looping: bool,
+    socket: Option<PathBuf>,
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(args) {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let mut recording = None;
+    let mut speed = 1.0;
+    let mut seek = Duration::from_secs(0);
+    let mut looping = false;
+    let mut socket = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+
+        match arg.as_str() {
+            "--speed" => {
+                let value = value("--speed")?;
+                speed = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|speed| speed.is_finite() && *speed > 0.0)
+                    .ok_or_else(|| format!("invalid speed {:?}", value))?;
+            }
+            "--seek" => seek = parse_offset(&value("--seek")?)?,
+            "--loop" => looping = true,
+            "--socket" => socket = Some(PathBuf::from(value("--socket")?)),
+            "-h" | "--help" => return Err("Plays a recording back into projectFly.".to_owned()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if recording.is_none() => recording = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    Ok(Args {
+        recording: recording.ok_or("no recording given")?,
+        speed,
+        seek,
+        looping,
+        socket,
+    })
+}
+
+/// Parses `SS`, `MM:SS` or `HH:MM:SS`, seconds may have a fraction.
+fn parse_offset(offset: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid offset {:?}", offset);
+
+    let mut seconds = 0.0;
+    for part in offset.split(':') {
+        let part: f64 = part.parse().map_err(|_| invalid())?;
+        if !(part.is_finite() && part >= 0.0) {
+            return Err(invalid());
+        }
+        seconds = seconds * 60.0 + part;
+    }
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+fn run(args: Args) -> pfly_rust::Result<()> {
+    let replay = Replay::open(&args.recording)?
+        .speed(args.speed)
+        .seek(args.seek)
+        .looping(args.looping);
+
+    info!(
+        "{}: {} frames over {:.1}s",
+        args.recording.display(),
+        replay.len(),
+        replay.duration().as_secs_f64()
+    );
+
+    let mut builder = PflyConnection::builder();
+    if let Some(socket) = &args.socket {
+        builder = builder.path(socket);
+    }
+    let mut connection = builder.connect()?;
+    info!(
+        "replaying to {} at {}x",
+        builder.socket_path().display(),
+        args.speed
+    );
+
+    let control = replay.control();
+    {
+        let control = control.clone();
+        ctrlc::set_handler(move || control.stop()).expect("could not install signal handler");
+    }
+    {
+        let control = control.clone();
+        thread::spawn(move || read_commands(control));
+    }
+
+    let sent = replay.run(|frame| connection.send(frame))?;
+    info!("sent {} frames", sent);
+
+    Ok(())
+}
+
+/// Toggles pause on every empty line, stops on `q`.
+fn read_commands(control: ReplayControl) {
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+
+        match line.trim() {
+            "" if control.is_paused() => {
+                info!("resuming");
+                control.resume();
+            }
+            "" => {
+                info!("paused, press Enter to resume");
+                control.pause();
+            }
+            "q" => {
+                control.stop();
+                return;
+            }
+            other => info!("unknown command {:?}, Enter pauses and q stops", other),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Sits between a bridge and projectFly and prints every frame that goes through.
+//!
+//! Usage: `pfly-sniff [--listen PATH] [--upstream PATH] [--json]`
+//!
+//! Point the bridge (or the native plugin) at the `--listen` socket, `/tmp/pf-sniff.sock` by
+//! default, and the bytes are passed on unchanged to projectFly at `--upstream`, which defaults
+//! to `$PFLY_SOCKET` or `/tmp/pf.sock`. Each frame is printed as a table of the fields that
+//! changed since the previous frame on the same connection, or with `--json` as one JSON object
+//! per line, for piping into `jq`.
+
+use log::{error, info, warn};
+use pfly_rust::{wire, PflyIpcData};
+use serde_json::{json, Map, Value};
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: pfly-sniff [--listen PATH] [--upstream PATH] [--json]";
+
+const DEFAULT_LISTEN_PATH: &str = "/tmp/pf-sniff.sock";
+
+/// Longest frame decoded, the same limit `wire::read_frame` has.
+const MAX_FRAME_LEN: u64 = (wire::FIXED_LEN + 8 + wire::MAX_AIRCRAFT_TYPE_LEN) as u64;
+
+/// Field order of `PflyIpcData`, for the table.
+const FIELDS: [&str; 22] = [
+    "altitude",
+    "agl",
+    "groundspeed",
+    "ias",
+    "headingTrue",
+    "headingMagnetic",
+    "latitude",
+    "longitude",
+    "verticalSpeed",
+    "landingVerticalSpeed",
+    "gForce",
+    "fuel",
+    "transponder",
+    "bridgeType",
+    "isOnGround",
+    "isSlew",
+    "isPaused",
+    "pitch",
+    "roll",
+    "time",
+    "fps",
+    "aircraftType",
+];
+
+#[derive(Debug)]
+struct Args {
+    listen: PathBuf,
+    upstream: PathBuf,
+    json: bool,
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(args) {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let mut listen = PathBuf::from(DEFAULT_LISTEN_PATH);
+    let mut upstream = pfly_rust::default_socket_path();
+    let mut json = false;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+
+        match arg.as_str() {
+            "--listen" => listen = PathBuf::from(value("--listen")?),
+            "--upstream" => upstream = PathBuf::from(value("--upstream")?),
+            "--json" => json = true,
+            "-h" | "--help" => {
+                return Err("Prints the frames a bridge sends to projectFly.".to_owned())
+            }
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    if listen == upstream {
+        return Err("--listen and --upstream are the same socket".to_owned());
+    }
+
+    Ok(Args {
+        listen,
+        upstream,
+        json,
+    })
+}
+
+fn run(args: Args) -> io::Result<()> {
+    let listener = bind(&args.listen)?;
+    info!(
+        "listening on {}, forwarding to {}",
+        args.listen.display(),
+        args.upstream.display()
+    );
+
+    {
+        let listen = args.listen.clone();
+        ctrlc::set_handler(move || {
+            let _ = std::fs::remove_file(&listen);
+            std::process::exit(0);
+        })
+        .expect("could not install signal handler");
+    }
+
+    let start = Instant::now();
+    for (id, client) in listener.incoming().enumerate() {
+        let client = match client {
+            Ok(client) => client,
+            Err(err) => {
+                warn!("could not accept connection: {}", err);
+                continue;
+            }
+        };
+
+        let upstream = match UnixStream::connect(&args.upstream) {
+            Ok(upstream) => upstream,
+            Err(err) => {
+                // Hanging up makes the bridge retry, just like projectFly not running would.
+                warn!(
+                    "connection {}: could not reach projectFly at {}: {}",
+                    id,
+                    args.upstream.display(),
+                    err
+                );
+                continue;
+            }
+        };
+        info!("connection {}: opened", id);
+
+        let sniffer = Sniffer {
+            id,
+            start,
+            json: args.json,
+            last: None,
+        };
+        thread::spawn(move || proxy(client, upstream, sniffer));
+    }
+
+    Ok(())
+}
+
+/// Binds `path`, removing a socket left over from an earlier run that nobody listens on.
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    if path.exists() && UnixStream::connect(path).is_err() {
+        std::fs::remove_file(path)?;
+    }
+
+    UnixListener::bind(path)
+}
+
+/// Copies bytes both ways until either side hangs up, decoding what the client sends.
+fn proxy(mut client: UnixStream, mut upstream: UnixStream, mut sniffer: Sniffer) {
+    // projectFly never answers, but whatever it might send should still get through.
+    if let (Ok(mut from), Ok(mut to)) = (upstream.try_clone(), client.try_clone()) {
+        thread::spawn(move || {
+            let _ = io::copy(&mut from, &mut to);
+            let _ = to.shutdown(Shutdown::Both);
+        });
+    }
+
+    let mut buffer = Vec::new();
+    let mut decoding = true;
+    let mut chunk = [0; 4096];
+
+    loop {
+        let read = match client.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                warn!("connection {}: read failed: {}", sniffer.id, err);
+                break;
+            }
+        };
+
+        if let Err(err) = upstream.write_all(&chunk[..read]) {
+            warn!("connection {}: projectFly went away: {}", sniffer.id, err);
+            break;
+        }
+
+        if decoding {
+            buffer.extend_from_slice(&chunk[..read]);
+            decoding = sniffer.drain(&mut buffer);
+        }
+    }
+
+    if !buffer.is_empty() && decoding {
+        warn!(
+            "connection {}: {} bytes of an unfinished frame left",
+            sniffer.id,
+            buffer.len()
+        );
+    }
+
+    let _ = upstream.shutdown(Shutdown::Both);
+    info!("connection {}: closed", sniffer.id);
+}
+
+struct Sniffer {
+    id: usize,
+    start: Instant,
+    json: bool,
+    last: Option<Map<String, Value>>,
+}
+
+impl Sniffer {
+    /// Prints every complete frame in `buffer` and removes it.
+    ///
+    /// Returns `false` once the stream can't be decoded any more, after that the bytes are
+    /// only forwarded.
+    fn drain(&mut self, buffer: &mut Vec<u8>) -> bool {
+        let mut offset = 0;
+
+        while let Some(len) = wire::frame_len(&buffer[offset..]) {
+            if len > MAX_FRAME_LEN {
+                // A corrupt length, waiting for that many bytes would keep everything forwarded.
+                error!(
+                    "connection {}: frame claims to be {} bytes long, no longer decoding",
+                    self.id, len
+                );
+                buffer.clear();
+                return false;
+            }
+            if len > (buffer.len() - offset) as u64 {
+                break;
+            }
+
+            match wire::decode(&buffer[offset..]) {
+                Ok((frame, len)) => {
+                    self.print(&frame);
+                    offset += len;
+                }
+                Err(err) => {
+                    // There is no way to find the start of the next frame again.
+                    error!("connection {}: {}, no longer decoding", self.id, err);
+                    buffer.clear();
+                    return false;
+                }
+            }
+        }
+
+        buffer.drain(..offset);
+        true
+    }
+
+    fn print(&mut self, frame: &PflyIpcData) {
+        let fields = match serde_json::to_value(frame) {
+            Ok(Value::Object(fields)) => fields,
+            _ => unreachable!("frames serialize to objects"),
+        };
+        let changed: Vec<&str> = FIELDS
+            .iter()
+            .copied()
+            .filter(|field| match &self.last {
+                Some(last) => last.get(*field) != fields.get(*field),
+                None => true,
+            })
+            .collect();
+
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        // stdout may be gone, e.g. piped into `head`, the proxy itself keeps going.
+        let _ = if self.json {
+            self.print_json(&mut out, &fields, &changed)
+        } else {
+            self.print_table(&mut out, &fields, &changed)
+        };
+
+        self.last = Some(fields);
+    }
+
+    fn print_json(
+        &self,
+        out: &mut impl Write,
+        fields: &Map<String, Value>,
+        changed: &[&str],
+    ) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        let changes: Map<String, Value> = match &self.last {
+            Some(last) => changed
+                .iter()
+                .map(|field| {
+                    let change = json!({ "from": last[*field], "to": fields[*field] });
+                    (field.to_string(), change)
+                })
+                .collect(),
+            None => Map::new(),
+        };
+
+        let line = json!({
+            "timestamp": timestamp,
+            "connection": self.id,
+            "frame": fields,
+            "changed": changes,
+        });
+        writeln!(out, "{}", line)
+    }
+
+    fn print_table(
+        &self,
+        out: &mut impl Write,
+        fields: &Map<String, Value>,
+        changed: &[&str],
+    ) -> io::Result<()> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+
+        if changed.is_empty() {
+            return writeln!(out, "[{:>9.3}s] #{} unchanged", elapsed, self.id);
+        }
+        writeln!(out, "[{:>9.3}s] #{}", elapsed, self.id)?;
+
+        for field in changed {
+            let (from, to) = match &self.last {
+                Some(last) => (&last[*field], &fields[*field]),
+                None => {
+                    writeln!(out, "  {:<22} {}", field, fields[*field])?;
+                    continue;
+                }
+            };
+
+            match (from.as_i64(), to.as_i64()) {
+                (Some(a), Some(b)) => {
+                    writeln!(out, "  {:<22} {} -> {} ({:+})", field, from, to, b - a)?
+                }
+                _ => writeln!(out, "  {:<22} {} -> {}", field, from, to)?,
+            }
+        }
+
+        Ok(())
+    }
+}
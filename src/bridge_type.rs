@@ -0,0 +1,71 @@
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// Which kind of simulator bridge a frame comes from.
+///
+/// projectFly sends this as an index into `bridgeTypes = ['simconnect', 'fsuipc', 'if', 'xplane']`,
+/// so it goes over the wire as that single byte.
+/// Anything coming through the X-Plane IPC socket should normally be [`BridgeType::XPlane`].
+///
+/// Indices projectFly may add later end up in [`BridgeType::Other`], so frames holding them
+/// still decode and pass through unchanged.
+///
+/// # Example
+///
+/// ```
+/// use pfly_rust::BridgeType;
+///
+/// assert_eq!(u8::from(BridgeType::XPlane), 3);
+/// assert_eq!(BridgeType::from(1), BridgeType::Fsuipc);
+/// assert_eq!(BridgeType::from(200), BridgeType::Other(200));
+/// assert_eq!(u8::from(BridgeType::Other(200)), 200);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BridgeType {
+    SimConnect,
+    Fsuipc,
+    InfiniteFlight,
+    #[default]
+    XPlane,
+    /// An index this crate doesn't know about (yet).
+    ///
+    /// [`BridgeType::from`] only makes this for indices above 3, an `Other` holding a known
+    /// index goes over the wire as that index and comes back as the named variant.
+    Other(u8),
+}
+
+impl From<BridgeType> for u8 {
+    fn from(bridge_type: BridgeType) -> u8 {
+        match bridge_type {
+            BridgeType::SimConnect => 0,
+            BridgeType::Fsuipc => 1,
+            BridgeType::InfiniteFlight => 2,
+            BridgeType::XPlane => 3,
+            BridgeType::Other(value) => value,
+        }
+    }
+}
+
+impl From<u8> for BridgeType {
+    fn from(value: u8) -> BridgeType {
+        match value {
+            0 => BridgeType::SimConnect,
+            1 => BridgeType::Fsuipc,
+            2 => BridgeType::InfiniteFlight,
+            3 => BridgeType::XPlane,
+            _ => BridgeType::Other(value),
+        }
+    }
+}
+
+impl Serialize for BridgeType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for BridgeType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BridgeType, D::Error> {
+        u8::deserialize(deserializer).map(BridgeType::from)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::units::{Angle, Length, Speed, VerticalSpeed};
+use crate::{BridgeType, PflyIpcData};
+use std::borrow::Cow;
+
+/// Builds a [`PflyIpcData`] from quantities with explicit units.
+///
+/// Every value is converted into the unit projectFly expects and rounded to the nearest
+/// whole number where the wire format wants an `i32`. Headings are wrapped into `0..360`
+/// and the G load is scaled by 1000 like projectFly expects.
+///
+/// Anything not set stays at zero, except the G load which starts at a steady 1 G.
+///
+/// # Example
+///
+/// ```
+/// use pfly_rust::units::{Angle, Length, Speed, VerticalSpeed};
+/// use pfly_rust::PflyIpcData;
+///
+/// let frame = PflyIpcData::builder()
+///     .altitude(Length::Meters(173.4))
+///     .ias(Speed::MetersPerSecond(77.2))
+///     .vertical_speed(VerticalSpeed::MetersPerSecond(-3.5))
+///     .heading_true(Angle::Degrees(-90.0))
+///     .position(Angle::Degrees(43.6772222), Angle::Degrees(-79.6305556))
+///     .g_force(1.23)
+///     .aircraft_type("B77W")
+///     .build();
+///
+/// assert_eq!(frame.altitude, 569);
+/// assert_eq!(frame.ias, 150);
+/// assert_eq!(frame.verticalSpeed, -689);
+/// assert_eq!(frame.headingTrue, 270);
+/// assert_eq!(frame.gForce, 1230);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PflyIpcDataBuilder {
+    data: PflyIpcData,
+}
+
+impl PflyIpcDataBuilder {
+    pub fn new() -> PflyIpcDataBuilder {
+        PflyIpcDataBuilder {
+            data: PflyIpcData {
+                gForce: 1000,
+                ..PflyIpcData::default()
+            },
+        }
+    }
+
+    /// Altitude above mean sea level.
+    pub fn altitude(mut self, altitude: Length) -> PflyIpcDataBuilder {
+        self.data.altitude = round(altitude.feet());
+        self
+    }
+
+    /// Height above ground level.
+    pub fn agl(mut self, agl: Length) -> PflyIpcDataBuilder {
+        self.data.agl = round(agl.feet());
+        self
+    }
+
+    pub fn groundspeed(mut self, groundspeed: Speed) -> PflyIpcDataBuilder {
+        self.data.groundspeed = round(groundspeed.knots());
+        self
+    }
+
+    /// Indicated airspeed.
+    pub fn ias(mut self, ias: Speed) -> PflyIpcDataBuilder {
+        self.data.ias = round(ias.knots());
+        self
+    }
+
+    pub fn heading_true(mut self, heading: Angle) -> PflyIpcDataBuilder {
+        self.data.headingTrue = heading_degrees(heading);
+        self
+    }
+
+    pub fn heading_magnetic(mut self, heading: Angle) -> PflyIpcDataBuilder {
+        self.data.headingMagnetic = heading_degrees(heading);
+        self
+    }
+
+    pub fn position(mut self, latitude: Angle, longitude: Angle) -> PflyIpcDataBuilder {
+        self.data.latitude = latitude.degrees();
+        self.data.longitude = longitude.degrees();
+        self
+    }
+
+    pub fn vertical_speed(mut self, vertical_speed: VerticalSpeed) -> PflyIpcDataBuilder {
+        self.data.verticalSpeed = round(vertical_speed.feet_per_minute());
+        self
+    }
+
+    /// Vertical speed at the moment of touchdown, negative for a descent.
+    pub fn landing_vertical_speed(mut self, vertical_speed: VerticalSpeed) -> PflyIpcDataBuilder {
+        self.data.landingVerticalSpeed = round(vertical_speed.feet_per_minute());
+        self
+    }
+
+    /// Normal load factor in G, `1.0` in level flight.
+    pub fn g_force(mut self, g_force: f64) -> PflyIpcDataBuilder {
+        self.data.gForce = round(g_force * 1000.0);
+        self
+    }
+
+    /// Fuel on board, passed through to projectFly as is.
+    pub fn fuel(mut self, fuel: i32) -> PflyIpcDataBuilder {
+        self.data.fuel = fuel;
+        self
+    }
+
+    /// The squawk code as its four digits, e.g. `7000`.
+    pub fn transponder(mut self, transponder: u16) -> PflyIpcDataBuilder {
+        self.data.transponder = i32::from(transponder);
+        self
+    }
+
+    pub fn bridge_type(mut self, bridge_type: BridgeType) -> PflyIpcDataBuilder {
+        self.data.bridgeType = bridge_type;
+        self
+    }
+
+    pub fn on_ground(mut self, on_ground: bool) -> PflyIpcDataBuilder {
+        self.data.isOnGround = on_ground;
+        self
+    }
+
+    pub fn slew(mut self, slew: bool) -> PflyIpcDataBuilder {
+        self.data.isSlew = slew;
+        self
+    }
+
+    pub fn paused(mut self, paused: bool) -> PflyIpcDataBuilder {
+        self.data.isPaused = paused;
+        self
+    }
+
+    /// Pitch, positive nose up.
+    pub fn pitch(mut self, pitch: Angle) -> PflyIpcDataBuilder {
+        self.data.pitch = round(pitch.degrees());
+        self
+    }
+
+    /// Roll, positive right wing down.
+    pub fn roll(mut self, roll: Angle) -> PflyIpcDataBuilder {
+        self.data.roll = round(roll.degrees());
+        self
+    }
+
+    pub fn fps(mut self, fps: f64) -> PflyIpcDataBuilder {
+        self.data.fps = round(fps);
+        self
+    }
+
+    /// The ICAO type designator, e.g. `"B77W"`.
+    pub fn aircraft_type<T: Into<Cow<'static, str>>>(
+        mut self,
+        aircraft_type: T,
+    ) -> PflyIpcDataBuilder {
+        self.data.aircraftType = aircraft_type.into();
+        self
+    }
+
+    pub fn build(self) -> PflyIpcData {
+        self.data
+    }
+}
+
+impl Default for PflyIpcDataBuilder {
+    fn default() -> PflyIpcDataBuilder {
+        PflyIpcDataBuilder::new()
+    }
+}
+
+/// Rounds to the nearest `i32`, saturating at the bounds and turning NaN into zero.
+pub(crate) fn round(value: f64) -> i32 {
+    value.round() as i32
+}
+
+pub(crate) fn heading_degrees(heading: Angle) -> i32 {
+    round(heading.degrees().rem_euclid(360.0)) % 360
+}
@@ -0,0 +1,446 @@
+use crate::landing::Landing;
+use crate::pipeline::Pipeline;
+use crate::record::Recorder;
+use crate::relay::{Key, SignedTransport};
+use crate::throttle::ChangeThresholds;
+use crate::transport::{TcpTransport, Transport, UdpTransport, UnixTransport};
+use crate::{wire, OverflowPolicy, PflyError, PflyIpcData, Result, SenderHandle};
+use socket2::Socket;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Where projectFly puts its socket when nothing else is configured.
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/pf.sock";
+
+/// Environment variable that overrides [`DEFAULT_SOCKET_PATH`].
+pub const SOCKET_PATH_ENV: &str = "PFLY_SOCKET";
+
+/// Works out which socket path to use when none was given explicitly.
+///
+/// This is `$PFLY_SOCKET` if it is set and not empty, otherwise `/tmp/pf.sock`.
+pub fn default_socket_path() -> PathBuf {
+    match env::var_os(SOCKET_PATH_ENV) {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(DEFAULT_SOCKET_PATH),
+    }
+}
+
+/// A connection to projectFly that stays open across messages.
+///
+/// Unlike passing the [`Socket`] around by hand, this owns the socket and only borrows each frame,
+/// so a telemetry loop can keep sending on the same stream for as long as projectFly is running.
+///
+/// The socket is projectFly's Unix socket by default, any other [`Transport`] works too.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn frame() -> pfly_rust::PflyIpcData { unimplemented!() }
+/// let mut connection = pfly_rust::PflyConnection::connect()?;
+///
+/// loop {
+///     connection.send(&frame())?;
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+/// }
+/// # Ok::<(), pfly_rust::PflyError>(())
+/// ```
+///
+/// [`Socket`]: https://docs.rs/socket2/0.3/socket2/struct.Socket.html
+/// [`Transport`]: crate::transport::Transport
+#[derive(Debug)]
+pub struct PflyConnection {
+    transport: Box<dyn Transport>,
+    pipeline: Pipeline,
+}
+
+impl PflyConnection {
+    /// Connects to projectFly the same way [`init`] does.
+    ///
+    /// [`init`]: crate::init
+    pub fn connect() -> Result<PflyConnection> {
+        PflyConnection::builder().connect()
+    }
+
+    /// Starts configuring a connection, see [`PflyConnectionBuilder`].
+    pub fn builder() -> PflyConnectionBuilder {
+        PflyConnectionBuilder::default()
+    }
+
+    /// Wraps a socket that is already connected to projectFly, e.g. one returned by [`init`].
+    ///
+    /// [`init`]: crate::init
+    pub fn new(socket: Socket) -> PflyConnection {
+        PflyConnection::with_transport(UnixTransport::new(socket))
+    }
+
+    /// Sends over `transport` instead of a Unix socket.
+    ///
+    /// Use [`PflyConnectionBuilder::with_transport`] to apply the other options as well.
+    pub fn with_transport<T: Transport + 'static>(transport: T) -> PflyConnection {
+        PflyConnection::from_box(Box::new(transport))
+    }
+
+    fn from_box(transport: Box<dyn Transport>) -> PflyConnection {
+        PflyConnection {
+            transport,
+            pipeline: Pipeline::default(),
+        }
+    }
+
+    /// Sends a single frame, leaving the connection open for the next one.
+    ///
+    /// In strict mode, frames that fail [`PflyIpcData::validate`] are refused with
+    /// [`PflyError::Invalid`] and never reach projectFly.
+    ///
+    /// With landing detection on, the landing fields are filled in before sending.
+    ///
+    /// With a rate limit, frames that come too soon or don't change enough are held back
+    /// and `Ok` is returned without sending anything, see [`flush`].
+    ///
+    /// With a recorder attached, the frame is recorded once it was sent. A
+    /// [`PflyError::Record`] therefore means projectFly did get the frame.
+    ///
+    /// [`flush`]: PflyConnection::flush
+    pub fn send(&mut self, data: &PflyIpcData) -> Result<()> {
+        match self.pipeline.prepare(data)? {
+            Some((data, now)) => self.write(&data, now),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the latest frame the rate limit held back, if there is one.
+    ///
+    /// Call this when the source goes quiet, so projectFly still ends up with the last state.
+    pub fn flush(&mut self) -> Result<()> {
+        match self.pipeline.take_pending() {
+            Some(data) => self.write(&data, Instant::now()),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, data: &PflyIpcData, now: Instant) -> Result<()> {
+        self.transport.send(&wire::encode(data))?;
+        self.pipeline.sent(data, now)
+    }
+
+    /// Turns strict mode on or off, see [`PflyConnectionBuilder::strict`].
+    pub fn set_strict(&mut self, strict: bool) {
+        self.pipeline.set_strict(strict);
+    }
+
+    /// Starts or stops recording sent frames, see [`PflyConnectionBuilder::recorder`].
+    pub fn set_recorder(&mut self, recorder: Option<Arc<Mutex<Recorder>>>) {
+        self.pipeline.set_recorder(recorder);
+    }
+
+    /// Turns landing detection on or off, see [`PflyConnectionBuilder::detect_landings`].
+    pub fn set_detect_landings(&mut self, detect_landings: bool) {
+        self.pipeline.set_detect_landings(detect_landings);
+    }
+
+    /// Changes the rate limit, see [`PflyConnectionBuilder::max_rate`] and
+    /// [`PflyConnectionBuilder::send_on_change`]. `None` for both sends every frame.
+    pub fn set_rate_limit(&mut self, max_rate: Option<f64>, thresholds: Option<ChangeThresholds>) {
+        self.pipeline.set_rate_limit(max_rate, thresholds);
+    }
+
+    /// The last landing seen on this connection, with landing detection on.
+    pub fn landing(&self) -> Option<Landing> {
+        self.pipeline.landing()
+    }
+
+    /// Sends the frame held back by the rate limit, if any, and hangs up.
+    pub fn close(mut self) -> Result<()> {
+        let flushed = self.flush();
+        let closed = self.transport.close();
+
+        flushed.and(closed)
+    }
+
+    /// Hands the connection to a thread of its own, see [`SenderHandle`].
+    ///
+    /// Up to [`SenderHandle::DEFAULT_CAPACITY`] frames are queued, after that the oldest
+    /// are dropped.
+    pub fn spawn(self) -> SenderHandle {
+        self.spawn_with(SenderHandle::DEFAULT_CAPACITY, OverflowPolicy::DropOldest)
+    }
+
+    /// Like [`spawn`], queueing up to `capacity` frames (at least one) and dropping frames
+    /// beyond that as `policy` says.
+    ///
+    /// [`spawn`]: PflyConnection::spawn
+    pub fn spawn_with(self, capacity: usize, policy: OverflowPolicy) -> SenderHandle {
+        SenderHandle::spawn(self, capacity, policy)
+    }
+
+    /// Returns the transport frames are sent over.
+    pub fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
+    }
+
+    /// Returns the underlying socket, `None` for transports that don't have one.
+    pub fn socket(&self) -> Option<&Socket> {
+        self.transport.socket()
+    }
+
+    /// Gives back the underlying socket, keeping it open.
+    ///
+    /// `None` for transports that don't have one, which are dropped instead.
+    pub fn into_socket(self) -> Option<Socket> {
+        self.transport.into_socket()
+    }
+}
+
+impl From<Socket> for PflyConnection {
+    fn from(socket: Socket) -> PflyConnection {
+        PflyConnection::new(socket)
+    }
+}
+
+/// Configures how to reach projectFly before connecting.
+///
+/// The socket path is picked in this order: the one given to [`path`], then the `PFLY_SOCKET`
+/// environment variable, then `/tmp/pf.sock`. With [`tcp`] or [`udp`], frames go to a network
+/// address instead.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// let connection = pfly_rust::PflyConnection::builder()
+///     .path("/run/user/1000/pf.sock")
+///     .connect_timeout(Duration::from_secs(2))
+///     .connect()?;
+/// # Ok::<(), pfly_rust::PflyError>(())
+/// ```
+///
+/// [`path`]: PflyConnectionBuilder::path
+/// [`tcp`]: PflyConnectionBuilder::tcp
+/// [`udp`]: PflyConnectionBuilder::udp
+#[derive(Debug, Clone, Default)]
+pub struct PflyConnectionBuilder {
+    pub(crate) target: Target,
+    pub(crate) relay_key: Option<Key>,
+    pub(crate) connect_timeout: Option<Duration>,
+    nonblocking: bool,
+    pub(crate) strict: bool,
+    pub(crate) recorder: Option<Arc<Mutex<Recorder>>>,
+    pub(crate) detect_landings: bool,
+    pub(crate) max_rate: Option<f64>,
+    pub(crate) change_thresholds: Option<ChangeThresholds>,
+}
+
+impl PflyConnectionBuilder {
+    /// Connects to the socket at `path` instead of looking it up.
+    pub fn path<P: AsRef<Path>>(mut self, path: P) -> PflyConnectionBuilder {
+        self.target = Target::Unix(Some(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Connects over TCP to `address`, like `"192.168.1.20:4500"`, see [`TcpTransport`].
+    ///
+    /// The address is resolved on every connect, so a host name may move around.
+    pub fn tcp<A: Into<String>>(mut self, address: A) -> PflyConnectionBuilder {
+        self.target = Target::Tcp(address.into());
+        self
+    }
+
+    /// Sends each frame as a UDP datagram to `address`, see [`UdpTransport`].
+    ///
+    /// Only `pfly-relay` listens for these, so this goes together with [`relay_key`].
+    ///
+    /// [`relay_key`]: PflyConnectionBuilder::relay_key
+    pub fn udp<A: Into<String>>(mut self, address: A) -> PflyConnectionBuilder {
+        self.target = Target::Udp(address.into());
+        self
+    }
+
+    /// Signs every frame with `key` for `pfly-relay`, see [`relay`].
+    ///
+    /// projectFly itself doesn't understand signed frames, so this only works together with
+    /// [`tcp`] or [`udp`] pointed at a relay. Connecting to a Unix socket with a key set fails
+    /// with [`PflyError::Misconfigured`].
+    ///
+    /// [`relay`]: crate::relay
+    /// [`tcp`]: PflyConnectionBuilder::tcp
+    /// [`udp`]: PflyConnectionBuilder::udp
+    pub fn relay_key<K: AsRef<[u8]>>(mut self, key: K) -> PflyConnectionBuilder {
+        self.relay_key = Some(Key::new(key));
+        self
+    }
+
+    /// Gives up connecting after `timeout` instead of waiting on the OS.
+    pub fn connect_timeout(mut self, timeout: Duration) -> PflyConnectionBuilder {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Puts the socket in non-blocking mode once connected.
+    ///
+    /// Sends then fail with a [`PflyError::Write`] of kind `WouldBlock` instead of waiting
+    /// when projectFly is not reading fast enough.
+    pub fn nonblocking(mut self, nonblocking: bool) -> PflyConnectionBuilder {
+        self.nonblocking = nonblocking;
+        self
+    }
+
+    /// Refuses to send frames that fail [`PflyIpcData::validate`], so garbage never ends up
+    /// in the pilot's logbook.
+    pub fn strict(mut self, strict: bool) -> PflyConnectionBuilder {
+        self.strict = strict;
+        self
+    }
+
+    /// Records every frame sent on the connection with `recorder`.
+    ///
+    /// Connections made from clones of this builder share the recorder, so a
+    /// [`ReconnectingConnection`] keeps writing to the same recording across reconnects.
+    ///
+    /// [`ReconnectingConnection`]: crate::ReconnectingConnection
+    pub fn recorder(self, recorder: Recorder) -> PflyConnectionBuilder {
+        self.shared_recorder(Arc::new(Mutex::new(recorder)))
+    }
+
+    /// Like [`recorder`], for a recorder that is also used elsewhere, e.g. to flush it.
+    ///
+    /// [`recorder`]: PflyConnectionBuilder::recorder
+    pub fn shared_recorder(mut self, recorder: Arc<Mutex<Recorder>>) -> PflyConnectionBuilder {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Fills in `landingVerticalSpeed` and the touchdown `gForce` with a [`LandingDetector`],
+    /// overriding whatever the frames had.
+    ///
+    /// Each connection starts with a fresh detector, so a landing during a reconnect is missed.
+    ///
+    /// [`LandingDetector`]: crate::landing::LandingDetector
+    pub fn detect_landings(mut self, detect_landings: bool) -> PflyConnectionBuilder {
+        self.detect_landings = detect_landings;
+        self
+    }
+
+    /// Sends at most `frames_per_second`, a source running at the simulator's frame rate
+    /// doesn't have to throttle itself.
+    ///
+    /// Frames in between are dropped, except for the latest one which [`PflyConnection::flush`]
+    /// still sends. Every frame that does go out is the newest one offered.
+    ///
+    /// A rate that isn't above zero, or so low the interval doesn't fit a `Duration`, means
+    /// no limit.
+    pub fn max_rate(mut self, frames_per_second: f64) -> PflyConnectionBuilder {
+        self.max_rate = Some(frames_per_second);
+        self
+    }
+
+    /// Only sends frames that changed noticeably since the last one sent, see [`ChangeThresholds`].
+    ///
+    /// Combines with [`max_rate`], a frame then has to be both due and changed.
+    ///
+    /// [`max_rate`]: PflyConnectionBuilder::max_rate
+    pub fn send_on_change(mut self, thresholds: ChangeThresholds) -> PflyConnectionBuilder {
+        self.change_thresholds = Some(thresholds);
+        self
+    }
+
+    /// The socket path this builder will connect to, unless it was told to use the network.
+    pub fn socket_path(&self) -> PathBuf {
+        match &self.target {
+            Target::Unix(Some(path)) => path.clone(),
+            _ => default_socket_path(),
+        }
+    }
+
+    /// The TCP address this builder will connect to, if any.
+    pub fn tcp_address(&self) -> Option<&str> {
+        match &self.target {
+            Target::Tcp(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// The UDP address this builder will send to, if any.
+    pub fn udp_address(&self) -> Option<&str> {
+        match &self.target {
+            Target::Udp(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Opens the connection.
+    pub fn connect(&self) -> Result<PflyConnection> {
+        self.check()?;
+
+        let transport: Box<dyn Transport> = match &self.target {
+            Target::Unix(_) => {
+                let transport = UnixTransport::connect(self.socket_path(), self.connect_timeout)?;
+                transport.set_nonblocking(self.nonblocking)?;
+                Box::new(transport)
+            }
+            Target::Tcp(address) => {
+                let transport = TcpTransport::connect(address.as_str(), self.connect_timeout)?;
+                transport.set_nonblocking(self.nonblocking)?;
+                Box::new(transport)
+            }
+            Target::Udp(address) => {
+                let transport = UdpTransport::connect(address.as_str())?;
+                transport
+                    .socket()
+                    .set_nonblocking(self.nonblocking)
+                    .map_err(PflyError::Socket)?;
+                Box::new(transport)
+            }
+        };
+
+        let transport = match &self.relay_key {
+            Some(key) => Box::new(SignedTransport::from_box(transport, key.clone())),
+            None => transport,
+        };
+
+        Ok(self.build(transport))
+    }
+
+    /// Refuses combinations of options that can't work, before connecting.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.relay_key.is_some() && matches!(self.target, Target::Unix(_)) {
+            return Err(PflyError::Misconfigured(
+                "a relay key needs a tcp or udp address, projectFly's socket takes unsigned frames",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Uses an already connected `transport` with the other options of this builder.
+    ///
+    /// The path, network address, relay key, connect timeout and non-blocking option
+    /// don't apply here.
+    pub fn with_transport<T: Transport + 'static>(&self, transport: T) -> PflyConnection {
+        self.build(Box::new(transport))
+    }
+
+    fn build(&self, transport: Box<dyn Transport>) -> PflyConnection {
+        PflyConnection {
+            transport,
+            pipeline: Pipeline::from_builder(self),
+        }
+    }
+}
+
+/// Where a builder connects to.
+#[derive(Debug, Clone)]
+pub(crate) enum Target {
+    /// projectFly's socket, at the default path if `None`.
+    Unix(Option<PathBuf>),
+    Tcp(String),
+    Udp(String),
+}
+
+impl Default for Target {
+    fn default() -> Target {
+        Target::Unix(None)
+    }
+}
@@ -0,0 +1,149 @@
+use crate::Violation;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while talking to projectFly.
+///
+/// Connection problems keep the underlying [`io::Error`] around so callers can inspect it,
+/// while the variant itself tells you which step failed.
+///
+/// [`io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
+#[derive(Debug)]
+pub enum PflyError {
+    /// The Unix socket itself could not be created.
+    Socket(io::Error),
+    /// The socket path could not be turned into a Unix socket address (e.g. it is too long).
+    Address(io::Error),
+    /// Nothing exists at the socket path, projectFly is most likely not running yet.
+    NotFound(io::Error),
+    /// The socket exists but nobody is accepting connections on it.
+    ConnectionRefused(io::Error),
+    /// Connecting failed for any other reason.
+    Connect(io::Error),
+    /// The payload could not be written to the socket.
+    Write(io::Error),
+    /// The frame failed [`PflyIpcData::validate`] and the connection is in strict mode.
+    ///
+    /// [`PflyIpcData::validate`]: crate::PflyIpcData::validate
+    Invalid(Vec<Violation>),
+    /// Reading from a simulator data source failed, including read timeouts.
+    Source(io::Error),
+    /// A simulator sent a packet that doesn't follow its protocol.
+    MalformedPacket(&'static str),
+    /// Bytes that don't follow the [`wire`] format of a frame.
+    ///
+    /// [`wire`]: crate::wire
+    MalformedFrame(&'static str),
+    /// A relayed frame wasn't signed with the shared key, see [`relay`].
+    ///
+    /// [`relay`]: crate::relay
+    Unauthenticated,
+    /// Writing or reading a telemetry recording failed.
+    Record(io::Error),
+    /// The options set on a [`PflyConnectionBuilder`] don't go together, e.g. a relay key
+    /// without a network address to send to.
+    ///
+    /// [`PflyConnectionBuilder`]: crate::PflyConnectionBuilder
+    Misconfigured(&'static str),
+    /// There is no open connection right now and the next reconnect attempt isn't due yet,
+    /// or the thread behind a [`SenderHandle`](crate::SenderHandle) stopped.
+    Disconnected,
+}
+
+impl PflyError {
+    /// Sorts an error from `connect` into the matching variant.
+    pub(crate) fn from_connect(err: io::Error) -> PflyError {
+        match err.kind() {
+            io::ErrorKind::NotFound => PflyError::NotFound(err),
+            io::ErrorKind::ConnectionRefused => PflyError::ConnectionRefused(err),
+            _ => PflyError::Connect(err),
+        }
+    }
+
+    /// Whether this error means projectFly went away (or never came up) and reconnecting may help.
+    ///
+    /// This covers a missing or refused socket, or a timed out or reset connection, on connect,
+    /// and broken pipes or resets on send. Other connect errors, like permission denied, won't
+    /// go away by retrying.
+    pub fn is_disconnect(&self) -> bool {
+        match self {
+            PflyError::NotFound(_) | PflyError::ConnectionRefused(_) => true,
+            PflyError::Connect(err) => matches!(
+                err.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+            ),
+            PflyError::Write(err) => matches!(
+                err.kind(),
+                io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+            ),
+            PflyError::Disconnected => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for PflyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PflyError::Socket(err) => write!(f, "could not create projectFly socket: {}", err),
+            PflyError::Address(err) => write!(f, "invalid projectFly socket address: {}", err),
+            PflyError::NotFound(_) => {
+                write!(f, "projectFly socket not found, is projectFly running?")
+            }
+            PflyError::ConnectionRefused(_) => write!(f, "projectFly refused the connection"),
+            PflyError::Connect(err) => write!(f, "could not connect to projectFly socket: {}", err),
+            PflyError::Write(err) => write!(f, "could not write to projectFly socket: {}", err),
+            PflyError::Invalid(violations) => {
+                write!(f, "refusing to send invalid frame: ")?;
+                for (i, violation) in violations.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", violation)?;
+                }
+                Ok(())
+            }
+            PflyError::Source(err) => write!(f, "could not read from simulator: {}", err),
+            PflyError::MalformedPacket(reason) => {
+                write!(f, "malformed simulator packet: {}", reason)
+            }
+            PflyError::MalformedFrame(reason) => {
+                write!(f, "malformed projectFly frame: {}", reason)
+            }
+            PflyError::Unauthenticated => write!(f, "frame is not signed with the shared key"),
+            PflyError::Record(err) => write!(f, "could not access recording: {}", err),
+            PflyError::Misconfigured(reason) => write!(f, "invalid connection options: {}", reason),
+            PflyError::Disconnected => write!(f, "not connected to projectFly"),
+        }
+    }
+}
+
+impl Error for PflyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PflyError::Socket(err)
+            | PflyError::Address(err)
+            | PflyError::NotFound(err)
+            | PflyError::ConnectionRefused(err)
+            | PflyError::Connect(err)
+            | PflyError::Write(err)
+            | PflyError::Source(err)
+            | PflyError::Record(err) => Some(err),
+            PflyError::Invalid(_)
+            | PflyError::MalformedPacket(_)
+            | PflyError::MalformedFrame(_)
+            | PflyError::Unauthenticated
+            | PflyError::Misconfigured(_)
+            | PflyError::Disconnected => None,
+        }
+    }
+}
+
+/// Shorthand for results returned by this crate.
+pub type Result<T> = std::result::Result<T, PflyError>;
@@ -0,0 +1,228 @@
+//! Captures the touchdown rate and G load, so bridges don't have to.
+//!
+//! By the time a simulator reports `isOnGround`, its vertical speed has usually already
+//! dropped to almost nothing, so taking the rate from that frame makes every landing look like
+//! a butter. [`LandingDetector`] keeps the last few frames around and takes the rate from
+//! just before contact instead.
+//!
+//! Turn it on for a connection with [`PflyConnectionBuilder::detect_landings`], or run frames
+//! through [`LandingDetector::apply`] yourself.
+//!
+//! ```
+//! use pfly_rust::landing::LandingDetector;
+//! use pfly_rust::PflyIpcData;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut detector = LandingDetector::new();
+//! let start = Instant::now();
+//!
+//! let mut flare = PflyIpcData { verticalSpeed: -180, gForce: 1050, ..PflyIpcData::default() };
+//! let mut contact = PflyIpcData { verticalSpeed: -20, gForce: 1380, isOnGround: true, ..PflyIpcData::default() };
+//!
+//! detector.apply_at(&mut flare, start);
+//! let landing = detector.apply_at(&mut contact, start + Duration::from_millis(50)).unwrap();
+//!
+//! assert_eq!(landing.vertical_speed, -180);
+//! assert_eq!(contact.landingVerticalSpeed, -180);
+//! assert_eq!(contact.gForce, 1380);
+//! ```
+//!
+//! [`PflyConnectionBuilder::detect_landings`]: crate::PflyConnectionBuilder::detect_landings
+
+use crate::PflyIpcData;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How far back before contact to look for the touchdown rate.
+const LOOKBACK: Duration = Duration::from_millis(500);
+
+/// How long after contact the G load keeps counting towards the peak.
+const SETTLE: Duration = Duration::from_secs(1);
+
+/// A touchdown, as captured by [`LandingDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Landing {
+    /// Vertical speed in feet per minute at the first contact, negative.
+    pub vertical_speed: i32,
+    /// Highest G load around the contacts, multiplied by 1000 like `gForce`.
+    pub g_force: i32,
+    /// How many times the aircraft bounced back into the air before staying down.
+    pub bounces: u32,
+    /// When the first contact happened.
+    pub at: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    vertical_speed: i32,
+    g_force: i32,
+    on_ground: bool,
+    at: Instant,
+}
+
+/// Watches frames for the air to ground transition and fills in the landing fields.
+///
+/// Once a landing was captured, every frame gets its `landingVerticalSpeed`, and for a second
+/// after each contact `gForce` holds the peak so far. Touching down again within the bounce
+/// window (5 seconds by default) counts as a bounce of the same landing: the rate of the first
+/// contact is kept and the peak G covers all of them.
+#[derive(Debug, Clone)]
+pub struct LandingDetector {
+    samples: VecDeque<Sample>,
+    capacity: usize,
+    bounce_window: Duration,
+    landing: Option<Landing>,
+    last_contact: Option<Instant>,
+    last_on_ground: Option<Instant>,
+}
+
+impl LandingDetector {
+    pub fn new() -> LandingDetector {
+        LandingDetector {
+            samples: VecDeque::new(),
+            capacity: 32,
+            bounce_window: Duration::from_secs(5),
+            landing: None,
+            last_contact: None,
+            last_on_ground: None,
+        }
+    }
+
+    /// How many recent frames to keep, enough to cover half a second at the frame rate used.
+    pub fn history(mut self, frames: usize) -> LandingDetector {
+        self.capacity = frames.max(2);
+        self
+    }
+
+    /// How long the aircraft may be back in the air for a contact to count as a bounce.
+    pub fn bounce_window(mut self, window: Duration) -> LandingDetector {
+        self.bounce_window = window;
+        self
+    }
+
+    /// The last landing captured, if any.
+    pub fn landing(&self) -> Option<Landing> {
+        self.landing
+    }
+
+    /// Forgets the history and the last landing.
+    pub fn reset(&mut self) {
+        *self = LandingDetector {
+            capacity: self.capacity,
+            bounce_window: self.bounce_window,
+            ..LandingDetector::new()
+        };
+    }
+
+    /// Looks at a frame that's about to be sent and fills in the landing fields.
+    ///
+    /// Returns the landing on every contact, with `bounces` counting up for bounces.
+    pub fn apply(&mut self, frame: &mut PflyIpcData) -> Option<Landing> {
+        self.apply_at(frame, Instant::now())
+    }
+
+    /// Like [`apply`], for a frame from `at`.
+    ///
+    /// [`apply`]: LandingDetector::apply
+    pub fn apply_at(&mut self, frame: &mut PflyIpcData, at: Instant) -> Option<Landing> {
+        let contact = if frame.isPaused || frame.isSlew {
+            None
+        } else {
+            self.observe(frame, at)
+        };
+
+        if let Some(landing) = self.landing {
+            frame.landingVerticalSpeed = landing.vertical_speed;
+
+            let settling = self
+                .last_contact
+                .is_some_and(|contact| at.saturating_duration_since(contact) <= SETTLE);
+            if frame.isOnGround && settling {
+                frame.gForce = landing.g_force;
+            }
+        }
+
+        contact
+    }
+
+    fn observe(&mut self, frame: &PflyIpcData, at: Instant) -> Option<Landing> {
+        let sample = Sample {
+            vertical_speed: frame.verticalSpeed,
+            g_force: frame.gForce,
+            on_ground: frame.isOnGround,
+            at,
+        };
+
+        let was_airborne = self.samples.back().is_some_and(|last| !last.on_ground);
+
+        self.samples.push_back(sample);
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+
+        if !sample.on_ground {
+            return None;
+        }
+
+        let previous_ground = self.last_on_ground.replace(at);
+
+        if !was_airborne {
+            // Rolling out, the G load still counts for a moment after contact.
+            if let (Some(landing), Some(contact)) = (self.landing.as_mut(), self.last_contact) {
+                if at.saturating_duration_since(contact) <= SETTLE {
+                    landing.g_force = landing.g_force.max(sample.g_force);
+                }
+            }
+            return None;
+        }
+
+        // Everything from the frames just before contact up to the contact itself.
+        let approach = self
+            .samples
+            .iter()
+            .rev()
+            .skip(1)
+            .take_while(|sample| !sample.on_ground)
+            .filter(|sample| at.saturating_duration_since(sample.at) <= LOOKBACK);
+        let last_airborne = self.samples.iter().rev().nth(1).copied();
+
+        let vertical_speed = approach
+            .clone()
+            .map(|sample| sample.vertical_speed)
+            .min()
+            .or_else(|| last_airborne.map(|sample| sample.vertical_speed))
+            .unwrap_or(sample.vertical_speed);
+        let g_force = approach
+            .map(|sample| sample.g_force)
+            .chain(std::iter::once(sample.g_force))
+            .max()
+            .unwrap_or(sample.g_force);
+
+        self.last_contact = Some(at);
+
+        let bounced = previous_ground
+            .is_some_and(|ground| at.saturating_duration_since(ground) <= self.bounce_window);
+
+        let landing = match self.landing.as_mut() {
+            Some(landing) if bounced => {
+                landing.bounces += 1;
+                landing.g_force = landing.g_force.max(g_force);
+                *landing
+            }
+            _ => *self.landing.insert(Landing {
+                vertical_speed,
+                g_force,
+                bounces: 0,
+                at,
+            }),
+        };
+
+        Some(landing)
+    }
+}
+
+impl Default for LandingDetector {
+    fn default() -> LandingDetector {
+        LandingDetector::new()
+    }
+}
@@ -5,47 +5,131 @@
 //! Creating a connection is super easy, calling [`init`] will give you a socket object that is bonded and connected to projectFly.
 //! You can then use [`send_message`] to send a message to projectFly with the structure of [`PflyIpcData`].
 //!
+//! For a telemetry loop, [`PflyConnection`] keeps that socket open and lets you send frame after frame,
+//! and [`ReconnectingConnection`] additionally picks projectFly back up when it gets restarted.
+//! [`PflyConnection::spawn`] moves the sending to a thread of its own, for callers that must never block.
+//! Both can also reach projectFly on another machine over TCP, see [`transport`],
+//! or send signed frames to `pfly-relay` running next to it, see [`relay`].
+//!
+//! With the `tokio` feature, [`async_client::AsyncPflyConnection`] does the same on top of Tokio.
+//!
+//! Frames can be read straight from a simulator with one of the [`sources`].
+//! [`phase::PhaseDetector`] follows them through taxi, takeoff, climb and so on,
+//! and [`landing::LandingDetector`] captures the touchdown rate and G load.
+//!
+//! Everything sent can be kept in a [`record`]ing, e.g. to attach to a bug report,
+//! and later played back into projectFly with [`replay`].
+//!
+//! For test traffic without a simulator, [`simulate`] makes up whole flights, reproducibly.
+//!
+//! The bytes that go over the socket are spelled out in [`wire`].
+//!
+//! To test a bridge without projectFly running, point it at a [`mock::MockServer`] instead.
+//!
+//! Everything returns a [`PflyError`] instead of panicking, so a bridge can simply retry while projectFly is still starting up.
+//!
 //! [`init`]: fn.init.html
 //! [`send_message`]: fn.send_message.html
 //! [`PflyIpcData`]: struct.PflyIpcData.html
+//! [`PflyError`]: enum.PflyError.html
+//! [`PflyConnection`]: struct.PflyConnection.html
+//! [`ReconnectingConnection`]: struct.ReconnectingConnection.html
+//! [`PflyConnection::spawn`]: struct.PflyConnection.html#method.spawn
+//! [`mock::MockServer`]: mock/struct.MockServer.html
+//! [`sources`]: sources/index.html
+//! [`phase::PhaseDetector`]: phase/struct.PhaseDetector.html
+//! [`landing::LandingDetector`]: landing/struct.LandingDetector.html
+//! [`record`]: record/index.html
+//! [`replay`]: replay/index.html
+//! [`simulate`]: simulate/index.html
+//! [`wire`]: wire/index.html
+//! [`transport`]: transport/index.html
+//! [`relay`]: relay/index.html
+//! [`async_client::AsyncPflyConnection`]: async_client/struct.AsyncPflyConnection.html
+
+#[cfg(feature = "tokio")]
+pub mod async_client;
+pub mod landing;
+pub mod mock;
+pub mod phase;
+pub mod record;
+pub mod relay;
+pub mod replay;
+pub mod simulate;
+pub mod sources;
+pub mod transport;
+pub mod units;
+pub mod wire;
+
+mod bridge_type;
+mod builder;
+mod connection;
+mod error;
+mod pipeline;
+mod reconnect;
+mod rng;
+mod sender;
+mod throttle;
+mod validate;
 
-use serde::Serialize;
-use socket2::{Domain, SockAddr, Socket, Type};
+pub use bridge_type::BridgeType;
+pub use builder::PflyIpcDataBuilder;
+pub use connection::{
+    default_socket_path, PflyConnection, PflyConnectionBuilder, DEFAULT_SOCKET_PATH,
+    SOCKET_PATH_ENV,
+};
+pub use error::{PflyError, Result};
+pub use reconnect::{Backoff, ConnectionState, ReconnectingConnection};
+pub use sender::{OverflowPolicy, SenderHandle};
+pub use throttle::ChangeThresholds;
+pub use validate::Violation;
 
-/// Connects to the projectFly Unix socket at `/tmp/pf.sock`.
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use socket2::Socket;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Write;
+
+/// Connects to the projectFly Unix socket at `$PFLY_SOCKET`, or `/tmp/pf.sock` if that isn't set.
+///
+/// Use [`PflyConnection::builder`] to pick the path yourself or set a timeout.
 ///
-/// Returns said socket for future use.
+/// Returns said socket for future use, or a [`PflyError`] describing which step failed.
+/// [`PflyError::NotFound`] and [`PflyError::ConnectionRefused`] usually just mean projectFly isn't up yet.
 ///
 /// # Example
 ///
+/// ```no_run
+/// let pfly_socket = pfly_rust::init().expect("projectFly is not running");
 /// ```
-/// let pfly_socket = pfly_rust::init();
-/// ```
-pub fn init() -> Socket {
-    let pfly_socket = Socket::new(Domain::unix(), Type::stream(), None).unwrap();
-    let pfly_socket_addr = &SockAddr::unix("/tmp/pf.sock").unwrap();
-
-    if pfly_socket.connect(pfly_socket_addr).is_err() {
-        panic!("Could not connect to projectFly socket!")
-    }
+///
+/// [`PflyError`]: enum.PflyError.html
+/// [`PflyError::NotFound`]: enum.PflyError.html#variant.NotFound
+/// [`PflyError::ConnectionRefused`]: enum.PflyError.html#variant.ConnectionRefused
+/// [`PflyConnection::builder`]: struct.PflyConnection.html#method.builder
+pub fn init() -> Result<Socket> {
+    let socket = transport::UnixTransport::connect(default_socket_path(), None)?;
 
-    return pfly_socket;
+    Ok(socket.into_socket())
 }
 
 /// Sends a message to the projectFly socket with a [`PflyIpcData`] payload converted into u8.
 ///
-/// Returns false if any errors ocurred when sending
+/// The payload is laid out as described in [`wire`].
+/// The whole payload is written, partial writes are retried until everything went out.
+/// Returns a [`PflyError`] if the payload could not be written.
 ///
 /// # Arguments
-/// * `pfly_socket` - The socket object from init()
+/// * `pfly_socket` - The socket object from init(), it is only borrowed and stays open
 /// * `data` - Information to be sent in the form of [`PflyIpcData`]
 ///
 /// # Example
 ///
-/// ```
-/// let pfly_socket = pfly_rust::init();
+/// ```no_run
+/// let pfly_socket = pfly_rust::init()?;
 ///
-/// pfly_rust::send_message(pfly_socket, pfly_rust::PflyIpcData{
+/// pfly_rust::send_message(&pfly_socket, &pfly_rust::PflyIpcData{
 ///     altitude: 569,
 ///     agl: 0,
 ///     groundspeed: 0,
@@ -59,7 +143,7 @@ pub fn init() -> Socket {
 ///     gForce: 1000, // Divided by 1000 by projectFly
 ///     fuel: 20000,
 ///     transponder: 1425,
-///     bridgeType: 3, // From projectFly: bridgeTypes = ['simconnect', 'fsuipc', 'if', 'xplane']
+///     bridgeType: pfly_rust::BridgeType::XPlane, // Sent as its index in projectFly's bridgeTypes
 ///     isOnGround: true,
 ///     isSlew: false,
 ///     isPaused: false,
@@ -67,22 +151,41 @@ pub fn init() -> Socket {
 ///     roll: 0,
 ///     time: 0, // This is calculated by projectFly
 ///     fps: 120,
-///     aircraftType: "B77W" // Unused by projectFly, still required just in case
-/// });
+///     aircraftType: "B77W".into() // Unused by projectFly, still required just in case
+/// })?;
+/// # Ok::<(), pfly_rust::PflyError>(())
 /// ```
 ///
 /// [`PflyIpcData`]: struct.PflyIpcData.html
-pub fn send_message(pfly_socket: Socket, data: PflyIpcData) -> bool {
-    let payload: Vec<u8> = bincode::serialize(&data).unwrap();
+/// [`PflyError`]: enum.PflyError.html
+/// [`wire`]: wire/index.html
+pub fn send_message(pfly_socket: &Socket, data: &PflyIpcData) -> Result<()> {
+    let payload: Vec<u8> = wire::encode(data);
 
-    return pfly_socket.send(payload.as_ref()).is_ok();
+    let mut pfly_socket = pfly_socket;
+    pfly_socket
+        .write_all(payload.as_ref())
+        .map_err(PflyError::Write)
 }
 
 /// Structure of data that projectFly expects over it's X-Plane IPC connection.
 ///
 /// As found in `/src/app/providers/flightsim.service.ts` of the projectFly source.
+///
+/// The fields use projectFly's units: feet, knots, feet per minute, degrees and G multiplied by 1000.
+/// [`PflyIpcData::builder`] converts from other units for you.
+///
+/// `aircraftType` is a [`Cow`] so it can hold a string literal without allocating,
+/// or a `String` read from the simulator at runtime.
+///
+/// With serde, a `latitude` or `longitude` that is NaN or infinite becomes the string `"NaN"`,
+/// `"inf"` or `"-inf"` in human readable formats like JSON, so it reads back instead of turning
+/// into `null`. Formats that aren't human readable get the plain `f64`.
+///
+/// [`Cow`]: https://doc.rust-lang.org/std/borrow/enum.Cow.html
+/// [`PflyIpcData::builder`]: struct.PflyIpcData.html#method.builder
 #[allow(non_snake_case)]
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct PflyIpcData {
     pub altitude: i32,
     pub agl: i32,
@@ -90,14 +193,22 @@ pub struct PflyIpcData {
     pub ias: i32,
     pub headingTrue: i32,
     pub headingMagnetic: i32,
+    #[serde(
+        serialize_with = "serialize_float",
+        deserialize_with = "deserialize_float"
+    )]
     pub latitude: f64,
+    #[serde(
+        serialize_with = "serialize_float",
+        deserialize_with = "deserialize_float"
+    )]
     pub longitude: f64,
     pub verticalSpeed: i32,
     pub landingVerticalSpeed: i32,
     pub gForce: i32,
     pub fuel: i32,
     pub transponder: i32,
-    pub bridgeType: u8,
+    pub bridgeType: BridgeType,
     pub isOnGround: bool,
     pub isSlew: bool,
     pub isPaused: bool,
@@ -105,5 +216,77 @@ pub struct PflyIpcData {
     pub roll: i32,
     pub time: i32, // This is calculated at projectFly
     pub fps: i32,
-    pub aircraftType: &'static str, // This isn't applied on the projectFly side for some reason, still adding it just incase
+    pub aircraftType: Cow<'static, str>, // This isn't applied on the projectFly side for some reason, still adding it just incase
+}
+
+impl PflyIpcData {
+    /// Starts building a frame from values with explicit units, see [`PflyIpcDataBuilder`].
+    ///
+    /// [`PflyIpcDataBuilder`]: struct.PflyIpcDataBuilder.html
+    pub fn builder() -> PflyIpcDataBuilder {
+        PflyIpcDataBuilder::new()
+    }
+}
+
+/// Writes NaN and infinities as `"NaN"`, `"inf"` and `"-inf"` in human readable formats like
+/// JSON, which has no numbers for them and would otherwise make them `null`.
+///
+/// Formats that aren't human readable, like bincode, get the plain `f64`.
+fn serialize_float<S: Serializer>(
+    value: &f64,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    if !serializer.is_human_readable() || value.is_finite() {
+        return serializer.serialize_f64(*value);
+    }
+
+    serializer.serialize_str(if value.is_nan() {
+        "NaN"
+    } else if *value > 0.0 {
+        "inf"
+    } else {
+        "-inf"
+    })
+}
+
+/// Reads what [`serialize_float`] wrote.
+fn deserialize_float<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<f64, D::Error> {
+    struct FloatVisitor;
+
+    impl<'de> Visitor<'de> for FloatVisitor {
+        type Value = f64;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a number, \"NaN\", \"inf\" or \"-inf\"")
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> std::result::Result<f64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> std::result::Result<f64, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> std::result::Result<f64, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<f64, E> {
+            match value {
+                "NaN" => Ok(f64::NAN),
+                "inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+            }
+        }
+    }
+
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(FloatVisitor)
+    } else {
+        f64::deserialize(deserializer)
+    }
 }
@@ -0,0 +1,285 @@
+//! A stand-in for projectFly, for testing bridges without the real app.
+//!
+//! [`MockServer`] listens on a Unix socket just like projectFly does on `/tmp/pf.sock`,
+//! decodes every frame it receives back into a [`PflyIpcData`] and hands them to the test.
+//! [`MockServer::start_tcp`] listens on a local TCP port instead, for testing the TCP transport.
+//!
+//! ```
+//! use pfly_rust::mock::MockServer;
+//! use std::time::Duration;
+//! # let frame = pfly_rust::PflyIpcData { altitude: 569, agl: 0, groundspeed: 0, ias: 0, headingTrue: 0,
+//! #     headingMagnetic: 0, latitude: 43.6772222, longitude: -79.6305556, verticalSpeed: 0,
+//! #     landingVerticalSpeed: 0, gForce: 1000, fuel: 20000, transponder: 1425, bridgeType: pfly_rust::BridgeType::XPlane,
+//! #     isOnGround: true, isSlew: false, isPaused: false, pitch: 0, roll: 0, time: 0, fps: 120,
+//! #     aircraftType: "B77W".into() };
+//!
+//! let server = MockServer::start()?;
+//! let mut connection = server.connection_builder().connect()?;
+//!
+//! connection.send(&frame)?;
+//!
+//! let received = server.recv_timeout(Duration::from_secs(1)).unwrap();
+//! assert_eq!(received.altitude, 569);
+//! assert_eq!(received.aircraftType, "B77W");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`PflyIpcData`]: crate::PflyIpcData
+
+use crate::{wire, PflyConnection, PflyConnectionBuilder, PflyIpcData};
+use std::io::{self, Read};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+static NEXT_SOCKET_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A fake projectFly listening on a Unix socket, or on TCP.
+///
+/// Frames are collected in the background and can be read with [`recv_timeout`], [`try_iter`]
+/// or [`iter`]. The socket file is removed again when the server is dropped.
+///
+/// [`recv_timeout`]: MockServer::recv_timeout
+/// [`try_iter`]: MockServer::try_iter
+/// [`iter`]: MockServer::iter
+#[derive(Debug)]
+pub struct MockServer {
+    path: Option<PathBuf>,
+    tcp_addr: Option<SocketAddr>,
+    frames: Receiver<PflyIpcData>,
+    connections: Arc<AtomicUsize>,
+    streams: Arc<Mutex<Vec<Stream>>>,
+    shutdown: Arc<AtomicBool>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server on a fresh socket in the temp directory.
+    pub fn start() -> io::Result<MockServer> {
+        let id = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("pfly-mock-{}-{}.sock", std::process::id(), id));
+
+        // Left over from an earlier run that was killed, it can't be in use by anyone else.
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+
+        MockServer::bind(path)
+    }
+
+    /// Starts a server on `path`, e.g. to emulate projectFly coming back after a restart.
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<MockServer> {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+
+        MockServer::serve(Listener::Unix(listener), Some(path), None)
+    }
+
+    /// Starts a server on a free TCP port on `127.0.0.1`.
+    pub fn start_tcp() -> io::Result<MockServer> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let addr = listener.local_addr()?;
+
+        MockServer::serve(Listener::Tcp(listener), None, Some(addr))
+    }
+
+    fn serve(
+        listener: Listener,
+        path: Option<PathBuf>,
+        tcp_addr: Option<SocketAddr>,
+    ) -> io::Result<MockServer> {
+        let (sender, frames) = mpsc::channel();
+
+        let connections = Arc::new(AtomicUsize::new(0));
+        let streams = Arc::new(Mutex::new(Vec::new()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let accept_thread = {
+            let connections = Arc::clone(&connections);
+            let streams = Arc::clone(&streams);
+            let shutdown = Arc::clone(&shutdown);
+
+            thread::spawn(move || accept(listener, sender, connections, streams, shutdown))
+        };
+
+        Ok(MockServer {
+            path,
+            tcp_addr,
+            frames,
+            connections,
+            streams,
+            shutdown,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    /// The socket path the server listens on.
+    ///
+    /// # Panics
+    ///
+    /// If the server was started with [`start_tcp`], use [`tcp_addr`] for those.
+    ///
+    /// [`start_tcp`]: MockServer::start_tcp
+    /// [`tcp_addr`]: MockServer::tcp_addr
+    pub fn path(&self) -> &Path {
+        self.path
+            .as_deref()
+            .expect("MockServer listens on TCP, not on a Unix socket")
+    }
+
+    /// The address the server listens on, if it was started with [`start_tcp`].
+    ///
+    /// [`start_tcp`]: MockServer::start_tcp
+    pub fn tcp_addr(&self) -> Option<SocketAddr> {
+        self.tcp_addr
+    }
+
+    /// A connection builder already pointed at this server.
+    pub fn connection_builder(&self) -> PflyConnectionBuilder {
+        match (&self.path, self.tcp_addr) {
+            (Some(path), _) => PflyConnection::builder().path(path),
+            (None, Some(addr)) => PflyConnection::builder().tcp(addr.to_string()),
+            (None, None) => unreachable!("MockServer listens somewhere"),
+        }
+    }
+
+    /// How many clients have connected so far.
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// Waits up to `timeout` for the next frame.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<PflyIpcData> {
+        match self.frames.recv_timeout(timeout) {
+            Ok(frame) => Some(frame),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Every frame received so far that hasn't been read yet, without waiting.
+    pub fn try_iter(&self) -> mpsc::TryIter<'_, PflyIpcData> {
+        self.frames.try_iter()
+    }
+
+    /// Blocks for each next frame, until the server is dropped.
+    pub fn iter(&self) -> mpsc::Iter<'_, PflyIpcData> {
+        self.frames.iter()
+    }
+
+    /// Hangs up on every connected client, like projectFly does when it is closed.
+    pub fn disconnect_all(&self) {
+        for stream in self.streams.lock().unwrap().drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.disconnect_all();
+
+        // Wake the accept loop up so it notices the shutdown flag.
+        if let Some(path) = &self.path {
+            let _ = UnixStream::connect(path);
+        }
+        if let Some(addr) = self.tcp_addr {
+            let _ = TcpStream::connect(addr);
+        }
+        if let Some(accept_thread) = self.accept_thread.take() {
+            let _ = accept_thread.join();
+        }
+
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Listener {
+    Unix(UnixListener),
+    Tcp(TcpListener),
+}
+
+impl Listener {
+    fn accept(&self) -> io::Result<Stream> {
+        match self {
+            Listener::Unix(listener) => listener.accept().map(|(stream, _)| Stream::Unix(stream)),
+            Listener::Tcp(listener) => listener.accept().map(|(stream, _)| Stream::Tcp(stream)),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Stream {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Stream {
+    fn try_clone(&self) -> io::Result<Stream> {
+        match self {
+            Stream::Unix(stream) => stream.try_clone().map(Stream::Unix),
+            Stream::Tcp(stream) => stream.try_clone().map(Stream::Tcp),
+        }
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        match self {
+            Stream::Unix(stream) => stream.shutdown(how),
+            Stream::Tcp(stream) => stream.shutdown(how),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Unix(stream) => stream.read(buf),
+            Stream::Tcp(stream) => stream.read(buf),
+        }
+    }
+}
+
+fn accept(
+    listener: Listener,
+    sender: Sender<PflyIpcData>,
+    connections: Arc<AtomicUsize>,
+    streams: Arc<Mutex<Vec<Stream>>>,
+    shutdown: Arc<AtomicBool>,
+) {
+    loop {
+        let stream = listener.accept();
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+
+        if let Ok(clone) = stream.try_clone() {
+            streams.lock().unwrap().push(clone);
+        }
+        connections.fetch_add(1, Ordering::SeqCst);
+
+        let sender = sender.clone();
+        thread::spawn(move || read_frames(stream, sender));
+    }
+}
+
+fn read_frames(mut stream: Stream, sender: Sender<PflyIpcData>) {
+    while let Ok(frame) = wire::read_frame(&mut stream) {
+        if sender.send(frame).is_err() {
+            break;
+        }
+    }
+}
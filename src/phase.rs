@@ -0,0 +1,239 @@
+//! Works out which phase of flight the aircraft is in from the frames sent to projectFly.
+//!
+//! projectFly keeps its own idea of the phase to itself, [`PhaseDetector`] makes a similar
+//! call for overlays and logs. Feed it every frame and it reports each [`PhaseChange`].
+//!
+//! ```
+//! use pfly_rust::phase::{FlightPhase, PhaseDetector};
+//! use pfly_rust::PflyIpcData;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut detector = PhaseDetector::new();
+//! let start = Instant::now();
+//!
+//! let parked = PflyIpcData { isOnGround: true, ..PflyIpcData::default() };
+//! let taxiing = PflyIpcData { groundspeed: 12, ias: 12, ..parked.clone() };
+//!
+//! detector.update_at(&parked, start);
+//! assert_eq!(detector.phase(), Some(FlightPhase::Boarding));
+//!
+//! // A change has to last a few seconds before it counts.
+//! assert!(detector.update_at(&taxiing, start + Duration::from_secs(10)).is_none());
+//! let change = detector.update_at(&taxiing, start + Duration::from_secs(13)).unwrap();
+//! assert_eq!(change.to, FlightPhase::Taxi);
+//! assert_eq!(change.at, start + Duration::from_secs(10));
+//! ```
+
+use crate::PflyIpcData;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Below this ground speed in knots the aircraft counts as stopped.
+const STOPPED_SPEED: i32 = 3;
+/// Indicated airspeed in knots on the ground that means a takeoff roll rather than taxiing.
+const TAKEOFF_ROLL_IAS: i32 = 40;
+/// Height above ground in feet where the takeoff turns into the climb.
+const TAKEOFF_HEIGHT: i32 = 1000;
+/// Heights above ground in feet to start and to give up an approach.
+const APPROACH_HEIGHT: i32 = 2500;
+const APPROACH_EXIT_HEIGHT: i32 = 3500;
+/// Vertical speeds in feet per minute to start and to keep climbing or descending.
+const CLIMB_RATE: i32 = 500;
+const CLIMB_EXIT_RATE: i32 = 200;
+
+/// A phase of flight, in the order a normal flight goes through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlightPhase {
+    /// On the ground and hasn't moved yet.
+    Boarding,
+    /// Moving on the ground before takeoff, including stops on the way.
+    Taxi,
+    /// The takeoff roll and the initial climb up to 1000 ft above the ground.
+    Takeoff,
+    Climb,
+    /// Roughly level flight, wherever it happens.
+    Cruise,
+    Descent,
+    /// Descending within 2500 ft of the ground.
+    Approach,
+    /// Back on the ground after flying, from touchdown to parking.
+    Landed,
+}
+
+impl fmt::Display for FlightPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FlightPhase::Boarding => "boarding",
+            FlightPhase::Taxi => "taxi",
+            FlightPhase::Takeoff => "takeoff",
+            FlightPhase::Climb => "climb",
+            FlightPhase::Cruise => "cruise",
+            FlightPhase::Descent => "descent",
+            FlightPhase::Approach => "approach",
+            FlightPhase::Landed => "landed",
+        })
+    }
+}
+
+/// A transition reported by [`PhaseDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseChange {
+    /// The previous phase, `None` for the very first frame.
+    pub from: Option<FlightPhase>,
+    pub to: FlightPhase,
+    /// When the first frame showing the new phase arrived.
+    pub at: Instant,
+}
+
+/// Turns a stream of frames into [`FlightPhase`] transitions.
+///
+/// Two kinds of hysteresis keep it from flapping: thresholds are harder to cross on the way in
+/// than on the way out, and a new phase has to show in every frame for [`hold`] (3 seconds by
+/// default) before it is reported. A bounced landing is one touchdown, not three.
+///
+/// Paused and slewing frames are ignored. Once the aircraft has flown, being on the ground
+/// is [`FlightPhase::Landed`] until [`reset`] is called for the next flight.
+///
+/// [`hold`]: PhaseDetector::hold
+/// [`reset`]: PhaseDetector::reset
+#[derive(Debug, Clone)]
+pub struct PhaseDetector {
+    hold: Duration,
+    phase: Option<FlightPhase>,
+    candidate: Option<(FlightPhase, Instant)>,
+    flown: bool,
+}
+
+impl PhaseDetector {
+    pub fn new() -> PhaseDetector {
+        PhaseDetector {
+            hold: Duration::from_secs(3),
+            phase: None,
+            candidate: None,
+            flown: false,
+        }
+    }
+
+    /// How long a new phase has to last before it is reported, zero reports it right away.
+    pub fn hold(mut self, hold: Duration) -> PhaseDetector {
+        self.hold = hold;
+        self
+    }
+
+    /// The current phase, `None` until the first frame.
+    pub fn phase(&self) -> Option<FlightPhase> {
+        self.phase
+    }
+
+    /// Forgets everything, e.g. before the next leg of a multi-leg session.
+    pub fn reset(&mut self) {
+        self.phase = None;
+        self.candidate = None;
+        self.flown = false;
+    }
+
+    /// Feeds a frame that was just received.
+    pub fn update(&mut self, frame: &PflyIpcData) -> Option<PhaseChange> {
+        self.update_at(frame, Instant::now())
+    }
+
+    /// Feeds a frame received at `at`, e.g. when replaying a recording.
+    pub fn update_at(&mut self, frame: &PflyIpcData, at: Instant) -> Option<PhaseChange> {
+        if frame.isPaused || frame.isSlew {
+            return None;
+        }
+
+        if !frame.isOnGround {
+            self.flown = true;
+        }
+
+        let seen = self.classify(frame);
+        let current = match self.phase {
+            Some(current) => current,
+            None => return Some(self.change(seen, at)),
+        };
+
+        if seen == current {
+            self.candidate = None;
+            return None;
+        }
+
+        let since = match self.candidate {
+            Some((candidate, since)) if candidate == seen => since,
+            _ => {
+                self.candidate = Some((seen, at));
+                at
+            }
+        };
+
+        if at.saturating_duration_since(since) >= self.hold {
+            Some(self.change(seen, since))
+        } else {
+            None
+        }
+    }
+
+    fn change(&mut self, to: FlightPhase, at: Instant) -> PhaseChange {
+        let from = self.phase.replace(to);
+        self.candidate = None;
+
+        PhaseChange { from, to, at }
+    }
+
+    /// The phase this frame looks like, given the phase we're in.
+    fn classify(&self, frame: &PflyIpcData) -> FlightPhase {
+        let vs = frame.verticalSpeed;
+        let agl = frame.agl;
+        let phase = self.phase;
+
+        if frame.isOnGround {
+            return if self.flown {
+                FlightPhase::Landed
+            } else if frame.ias >= TAKEOFF_ROLL_IAS {
+                FlightPhase::Takeoff
+            } else if frame.groundspeed >= STOPPED_SPEED {
+                FlightPhase::Taxi
+            } else {
+                match phase {
+                    Some(FlightPhase::Taxi) | Some(FlightPhase::Takeoff) => FlightPhase::Taxi,
+                    _ => FlightPhase::Boarding,
+                }
+            };
+        }
+
+        match phase {
+            Some(FlightPhase::Takeoff) if agl < TAKEOFF_HEIGHT && vs > -CLIMB_RATE => {
+                return FlightPhase::Takeoff
+            }
+            Some(FlightPhase::Approach) if agl < APPROACH_EXIT_HEIGHT && vs < CLIMB_RATE => {
+                return FlightPhase::Approach
+            }
+            _ => {}
+        }
+
+        let climb_rate = match phase {
+            Some(FlightPhase::Climb) => CLIMB_EXIT_RATE,
+            _ => CLIMB_RATE,
+        };
+        let descent_rate = match phase {
+            Some(FlightPhase::Descent) => CLIMB_EXIT_RATE,
+            _ => CLIMB_RATE,
+        };
+
+        if vs < -CLIMB_EXIT_RATE && agl < APPROACH_HEIGHT {
+            FlightPhase::Approach
+        } else if vs > climb_rate {
+            FlightPhase::Climb
+        } else if vs < -descent_rate {
+            FlightPhase::Descent
+        } else {
+            FlightPhase::Cruise
+        }
+    }
+}
+
+impl Default for PhaseDetector {
+    fn default() -> PhaseDetector {
+        PhaseDetector::new()
+    }
+}
@@ -0,0 +1,111 @@
+use crate::landing::{Landing, LandingDetector};
+use crate::record::Recorder;
+use crate::throttle::{ChangeThresholds, Throttle};
+use crate::{PflyConnectionBuilder, PflyError, PflyIpcData, Result};
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Everything that happens to a frame around the write itself.
+///
+/// Before: strict validation, landing detection and the rate limit. After: telling the rate
+/// limit and recording. [`PflyConnection`] and the async connection only differ in how they
+/// write, so both go through this.
+///
+/// [`PflyConnection`]: crate::PflyConnection
+#[derive(Debug, Default)]
+pub(crate) struct Pipeline {
+    strict: bool,
+    recorder: Option<Arc<Mutex<Recorder>>>,
+    landings: Option<LandingDetector>,
+    throttle: Option<Throttle>,
+}
+
+impl Pipeline {
+    /// With the strict mode, recorder, landing detection and rate limit of `builder`.
+    pub(crate) fn from_builder(builder: &PflyConnectionBuilder) -> Pipeline {
+        let mut pipeline = Pipeline::default();
+        pipeline.set_strict(builder.strict);
+        pipeline.set_recorder(builder.recorder.clone());
+        pipeline.set_detect_landings(builder.detect_landings);
+        pipeline.set_rate_limit(builder.max_rate, builder.change_thresholds);
+
+        pipeline
+    }
+
+    /// The frame to write now and when it was offered, `None` if the rate limit held it back.
+    pub(crate) fn prepare<'a>(
+        &mut self,
+        data: &'a PflyIpcData,
+    ) -> Result<Option<(Cow<'a, PflyIpcData>, Instant)>> {
+        if self.strict {
+            data.validate().map_err(PflyError::Invalid)?;
+        }
+
+        let data = match self.landings.as_mut() {
+            Some(detector) => {
+                let mut data = data.clone();
+                detector.apply(&mut data);
+                Cow::Owned(data)
+            }
+            None => Cow::Borrowed(data),
+        };
+
+        let now = Instant::now();
+        if let Some(throttle) = self.throttle.as_mut() {
+            if !throttle.offer(&data, now) {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some((data, now)))
+    }
+
+    /// The latest frame the rate limit held back, if there is one.
+    pub(crate) fn take_pending(&mut self) -> Option<PflyIpcData> {
+        self.throttle.as_mut().and_then(Throttle::take_pending)
+    }
+
+    /// Call once `data`, offered at `now`, was written.
+    pub(crate) fn sent(&mut self, data: &PflyIpcData, now: Instant) -> Result<()> {
+        if let Some(throttle) = self.throttle.as_mut() {
+            throttle.sent(data, now);
+        }
+
+        match &self.recorder {
+            Some(recorder) => recorder
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .record(data),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub(crate) fn set_recorder(&mut self, recorder: Option<Arc<Mutex<Recorder>>>) {
+        self.recorder = recorder;
+    }
+
+    pub(crate) fn set_detect_landings(&mut self, detect_landings: bool) {
+        self.landings = if detect_landings {
+            Some(LandingDetector::new())
+        } else {
+            None
+        };
+    }
+
+    pub(crate) fn set_rate_limit(
+        &mut self,
+        max_rate: Option<f64>,
+        thresholds: Option<ChangeThresholds>,
+    ) {
+        self.throttle = Throttle::new(max_rate, thresholds);
+    }
+
+    pub(crate) fn landing(&self) -> Option<Landing> {
+        self.landings.as_ref().and_then(LandingDetector::landing)
+    }
+}
@@ -0,0 +1,284 @@
+use crate::rng::XorShift;
+use crate::{PflyConnection, PflyConnectionBuilder, PflyError, PflyIpcData, Result};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Where a [`ReconnectingConnection`] currently stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No connection has been attempted yet, or the last one was just lost.
+    Disconnected,
+    /// The last `attempt` connection attempts failed, another one follows after a backoff delay.
+    Reconnecting { attempt: u32 },
+    /// Frames are going out to projectFly.
+    Connected,
+}
+
+/// Exponential backoff between reconnect attempts.
+///
+/// The n-th retry waits `initial * multiplier^(n - 1)`, capped at `max`, and then randomly
+/// shifted by up to `jitter` (a fraction, `0.2` being ±20%) so several bridges don't all hammer
+/// projectFly in lockstep after it restarts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+    pub jitter: f64,
+}
+
+impl Backoff {
+    /// The delay before retry number `attempt` (starting at 1), without jitter.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial.as_secs_f64() * self.multiplier.powi(exponent);
+
+        // The fields are public, so anything goes: a negative multiplier ends up at 0,
+        // NaN and overflows at `max`.
+        Duration::try_from_secs_f64(delay.min(self.max.as_secs_f64()))
+            .unwrap_or(Duration::ZERO)
+            .min(self.max)
+    }
+
+    fn jittered(&self, attempt: u32, rng: &mut XorShift) -> Duration {
+        let jitter = if self.jitter.is_nan() {
+            0.0
+        } else {
+            self.jitter.clamp(0.0, 1.0)
+        };
+        let factor = 1.0 - jitter + 2.0 * jitter * rng.next_f64();
+
+        let delay = self.delay(attempt).as_secs_f64() * factor;
+        Duration::try_from_secs_f64(delay).unwrap_or(self.max)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Backoff {
+        Backoff {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+/// A connection that survives projectFly being closed and reopened.
+///
+/// When a send fails because the socket went away (broken pipe, reset, missing socket file),
+/// the connection is dropped and re-established on a later call once the [`Backoff`] delay has passed.
+/// Calls made while waiting return [`PflyError::Disconnected`] straight away instead of blocking,
+/// so a telemetry loop can just keep going.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn frame() -> pfly_rust::PflyIpcData { unimplemented!() }
+/// use pfly_rust::{PflyConnection, ReconnectingConnection};
+///
+/// let mut connection = ReconnectingConnection::new(PflyConnection::builder())
+///     .buffer_latest(true)
+///     .on_state_change(|state| println!("projectFly is now {:?}", state));
+///
+/// loop {
+///     if let Err(err) = connection.send(&frame()) {
+///         if !err.is_disconnect() {
+///             panic!("{}", err);
+///         }
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+/// }
+/// ```
+pub struct ReconnectingConnection {
+    builder: PflyConnectionBuilder,
+    connection: Option<PflyConnection>,
+    backoff: Backoff,
+    state: ConnectionState,
+    next_attempt: Option<Instant>,
+    buffer_latest: bool,
+    pending: Option<PflyIpcData>,
+    on_state_change: Option<Box<dyn FnMut(ConnectionState) + Send>>,
+    rng: XorShift,
+}
+
+impl ReconnectingConnection {
+    /// Creates a client that connects with `builder`, lazily on the first send.
+    pub fn new(builder: PflyConnectionBuilder) -> ReconnectingConnection {
+        ReconnectingConnection {
+            builder,
+            connection: None,
+            backoff: Backoff::default(),
+            state: ConnectionState::Disconnected,
+            next_attempt: None,
+            buffer_latest: false,
+            pending: None,
+            on_state_change: None,
+            rng: XorShift::from_time(),
+        }
+    }
+
+    /// Replaces the default backoff of 250ms doubling up to 30s with ±20% jitter.
+    pub fn backoff(mut self, backoff: Backoff) -> ReconnectingConnection {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Keeps the latest frame that could not be sent and delivers it on reconnect.
+    ///
+    /// Only one frame is kept, older ones are stale by the time projectFly is back anyway.
+    pub fn buffer_latest(mut self, buffer_latest: bool) -> ReconnectingConnection {
+        self.buffer_latest = buffer_latest;
+        self
+    }
+
+    /// Calls `callback` every time the [`ConnectionState`] changes.
+    ///
+    /// To get the states on a channel instead, move the `Sender` into the callback.
+    pub fn on_state_change<F>(mut self, callback: F) -> ReconnectingConnection
+    where
+        F: FnMut(ConnectionState) + Send + 'static,
+    {
+        self.on_state_change = Some(Box::new(callback));
+        self
+    }
+
+    /// The current connection state.
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Whether a frame is waiting to be delivered on reconnect.
+    pub fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Sends a frame, reconnecting first if needed and the backoff delay has passed.
+    ///
+    /// A successful send replaces any buffered frame, since this one is newer.
+    pub fn send(&mut self, data: &PflyIpcData) -> Result<()> {
+        let result = self.ensure_connected().and_then(|_| self.send_now(data));
+
+        match &result {
+            Ok(()) => self.pending = None,
+            Err(err) if err.is_disconnect() && self.buffer_latest => {
+                self.pending = Some(data.clone())
+            }
+            Err(_) => {}
+        }
+
+        result
+    }
+
+    /// Reconnects if due and flushes the buffered frame, without a new frame to send.
+    ///
+    /// Useful when the data source stalls while projectFly is down, so the last frame
+    /// still arrives as soon as projectFly is back.
+    pub fn poll(&mut self) -> Result<()> {
+        self.ensure_connected()?;
+
+        match self.pending.take() {
+            Some(data) => self.send(&data),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the latest frame the connection's rate limit held back, see [`PflyConnection::flush`].
+    ///
+    /// Does nothing while disconnected.
+    pub fn flush(&mut self) -> Result<()> {
+        let connection = match self.connection.as_mut() {
+            Some(connection) => connection,
+            None => return Ok(()),
+        };
+
+        let result = connection.flush();
+        if let Err(err) = &result {
+            if err.is_disconnect() {
+                self.disconnect();
+            }
+        }
+
+        result
+    }
+
+    /// Drops the current connection, the next send reconnects.
+    pub fn disconnect(&mut self) {
+        self.connection = None;
+        self.next_attempt = None;
+        self.set_state(ConnectionState::Disconnected);
+    }
+
+    fn ensure_connected(&mut self) -> Result<()> {
+        if self.connection.is_some() {
+            return Ok(());
+        }
+
+        if let Some(next_attempt) = self.next_attempt {
+            if Instant::now() < next_attempt {
+                return Err(PflyError::Disconnected);
+            }
+        }
+
+        match self.builder.connect() {
+            Ok(connection) => {
+                self.connection = Some(connection);
+                self.next_attempt = None;
+                self.set_state(ConnectionState::Connected);
+                Ok(())
+            }
+            Err(err) => {
+                let attempt = match self.state {
+                    ConnectionState::Reconnecting { attempt } => attempt.saturating_add(1),
+                    _ => 1,
+                };
+
+                self.next_attempt =
+                    Some(Instant::now() + self.backoff.jittered(attempt, &mut self.rng));
+                self.set_state(ConnectionState::Reconnecting { attempt });
+                Err(err)
+            }
+        }
+    }
+
+    fn send_now(&mut self, data: &PflyIpcData) -> Result<()> {
+        let connection = match self.connection.as_mut() {
+            Some(connection) => connection,
+            None => return Err(PflyError::Disconnected),
+        };
+
+        let result = connection.send(data);
+        if let Err(err) = &result {
+            if err.is_disconnect() {
+                self.disconnect();
+            }
+        }
+
+        result
+    }
+
+    fn set_state(&mut self, state: ConnectionState) {
+        if self.state == state {
+            return;
+        }
+
+        self.state = state;
+        if let Some(callback) = self.on_state_change.as_mut() {
+            callback(state);
+        }
+    }
+}
+
+impl fmt::Debug for ReconnectingConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectingConnection")
+            .field("builder", &self.builder)
+            .field("connection", &self.connection)
+            .field("backoff", &self.backoff)
+            .field("state", &self.state)
+            .field("next_attempt", &self.next_attempt)
+            .field("buffer_latest", &self.buffer_latest)
+            .field("has_pending", &self.pending.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,221 @@
+//! Records every frame sent to projectFly, so a wrongly scored flight can be looked at later.
+//!
+//! Recordings are [JSON Lines] files, one [`Record`] per line holding the frame and when it was
+//! sent. They are named after the time recording started (`pfly-<unix seconds>.jsonl`) and can
+//! be rotated once they reach a certain size.
+//!
+//! Attach a [`Recorder`] to a connection with [`PflyConnectionBuilder::recorder`], every frame
+//! that was sent successfully is then written to it as well.
+//!
+//! ```
+//! use pfly_rust::mock::MockServer;
+//! use pfly_rust::record::{self, Recorder};
+//! use pfly_rust::units::Length;
+//! use pfly_rust::PflyIpcData;
+//!
+//! # let dir = std::env::temp_dir().join(format!("pfly-record-doc-{}", std::process::id()));
+//! let server = MockServer::start()?;
+//! let recorder = Recorder::create(&dir)?.max_file_size(16 * 1024 * 1024);
+//! let path = recorder.path().to_path_buf();
+//!
+//! let mut connection = server.connection_builder().recorder(recorder).connect()?;
+//! connection.send(&PflyIpcData::builder().altitude(Length::Feet(569.0)).build())?;
+//! # drop(connection);
+//!
+//! let records = record::read(&path)?.collect::<Result<Vec<_>, _>>()?;
+//! assert_eq!(records[0].frame.altitude, 569);
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [JSON Lines]: https://jsonlines.org
+//! [`PflyConnectionBuilder::recorder`]: crate::PflyConnectionBuilder::recorder
+
+use crate::{PflyError, PflyIpcData, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One line of a recording.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Record {
+    /// When the frame was sent, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub frame: PflyIpcData,
+}
+
+/// Writes frames to a recording, starting a new file whenever the current one gets too big.
+///
+/// Lines are buffered, call [`flush`] to make sure everything is on disk. Dropping the recorder
+/// flushes as well.
+///
+/// [`flush`]: Recorder::flush
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    writer: BufWriter<File>,
+    path: PathBuf,
+    written: u64,
+    max_file_size: Option<u64>,
+    max_files: Option<usize>,
+    files: VecDeque<PathBuf>,
+}
+
+impl Recorder {
+    /// Starts a recording in `dir`, creating the directory if needed.
+    pub fn create<P: AsRef<Path>>(dir: P) -> Result<Recorder> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(PflyError::Record)?;
+
+        let (path, file) = open_new(&dir)?;
+
+        Ok(Recorder {
+            dir,
+            writer: BufWriter::new(file),
+            path: path.clone(),
+            written: 0,
+            max_file_size: None,
+            max_files: None,
+            files: vec![path].into(),
+        })
+    }
+
+    /// Starts a new file once the current one has reached `bytes`.
+    pub fn max_file_size(mut self, bytes: u64) -> Recorder {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Deletes the oldest files of this recording so at most `files` are kept.
+    ///
+    /// Files from earlier recordings in the same directory are left alone.
+    pub fn max_files(mut self, files: usize) -> Recorder {
+        self.max_files = Some(files.max(1));
+        self
+    }
+
+    /// The file currently being written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records `frame` as sent right now.
+    pub fn record(&mut self, frame: &PflyIpcData) -> Result<()> {
+        self.record_at(frame, SystemTime::now())
+    }
+
+    /// Records `frame` as sent at `time`.
+    pub fn record_at(&mut self, frame: &PflyIpcData, time: SystemTime) -> Result<()> {
+        let timestamp = time
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or(0);
+
+        let record = Record {
+            timestamp,
+            frame: frame.clone(),
+        };
+
+        let mut line = serde_json::to_vec(&record).map_err(|err| PflyError::Record(err.into()))?;
+        line.push(b'\n');
+
+        if let Some(max_file_size) = self.max_file_size {
+            if self.written > 0 && self.written + line.len() as u64 > max_file_size {
+                self.rotate()?;
+            }
+        }
+
+        self.writer.write_all(&line).map_err(PflyError::Record)?;
+        self.written += line.len() as u64;
+
+        Ok(())
+    }
+
+    /// Writes buffered lines out to the file.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(PflyError::Record)
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.flush()?;
+
+        let (path, file) = open_new(&self.dir)?;
+        self.writer = BufWriter::new(file);
+        self.path = path.clone();
+        self.written = 0;
+        self.files.push_back(path);
+
+        if let Some(max_files) = self.max_files {
+            while self.files.len() > max_files {
+                if let Some(oldest) = self.files.pop_front() {
+                    fs::remove_file(oldest).map_err(PflyError::Record)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates `pfly-<unix seconds>.jsonl` in `dir`, with a counter appended if that is taken.
+fn open_new(dir: &Path) -> Result<(PathBuf, File)> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0);
+
+    for n in 0.. {
+        let name = match n {
+            0 => format!("pfly-{}.jsonl", seconds),
+            n => format!("pfly-{}-{}.jsonl", seconds, n),
+        };
+        let path = dir.join(name);
+
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(PflyError::Record(err)),
+        }
+    }
+
+    unreachable!("ran out of recording file names")
+}
+
+/// Opens a recording for reading, see [`Records`].
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Records> {
+    let file = File::open(path).map_err(PflyError::Record)?;
+
+    Ok(Records {
+        lines: BufReader::new(file).lines(),
+    })
+}
+
+/// The records of a recording, in the order they were written.
+///
+/// Blank lines are skipped, a line that isn't a valid record ends up as an error.
+#[derive(Debug)]
+pub struct Records {
+    lines: io::Lines<BufReader<File>>,
+}
+
+impl Iterator for Records {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Result<Record>> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(PflyError::Record(err))),
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(serde_json::from_str(&line).map_err(|err| PflyError::Record(err.into())));
+        }
+    }
+}
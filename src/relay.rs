@@ -0,0 +1,358 @@
+//! Signed frames, for sending telemetry to projectFly on another machine through `pfly-relay`.
+//!
+//! projectFly only listens on its local Unix socket. `pfly-relay` runs next to it, accepts
+//! frames over TCP or UDP and passes them on. So that not everyone on the network can fly for
+//! you, each frame travels in an envelope signed with a key shared between the bridge and the
+//! relay:
+//!
+//! | Offset | Size | Field                                                      |
+//! |-------:|-----:|------------------------------------------------------------|
+//! |      0 |    4 | `PFR1`                                                     |
+//! |      4 |    8 | when the frame was sent, milliseconds since the Unix epoch |
+//! |     12 |    8 | sequence number, counting up per connection                |
+//! |     20 |    4 | length of the frame                                        |
+//! |     24 |    n | the frame, as laid out in [`wire`]                         |
+//! | 24 + n |   32 | HMAC-SHA256 of everything before, keyed with the shared key |
+//!
+//! All numbers are little-endian. Over UDP every datagram holds one envelope, over TCP they
+//! follow each other on the stream.
+//!
+//! A bridge picks this up with [`PflyConnectionBuilder::relay_key`] together with
+//! [`PflyConnectionBuilder::tcp`] or [`PflyConnectionBuilder::udp`]. The relay checks envelopes
+//! with a [`Verifier`].
+//!
+//! ```
+//! use pfly_rust::relay::{self, Verifier};
+//! use pfly_rust::{wire, PflyIpcData};
+//! use std::time::SystemTime;
+//!
+//! let frame = PflyIpcData { altitude: 569, ..PflyIpcData::default() };
+//! let envelope = relay::seal(b"correct horse battery staple", &wire::encode(&frame), 1, SystemTime::now());
+//!
+//! let mut verifier = Verifier::new("correct horse battery staple");
+//! let source = "192.168.1.20".parse()?;
+//! assert_eq!(verifier.verify(source, &envelope).unwrap().frame, frame);
+//!
+//! // The same envelope again is a replay.
+//! assert!(verifier.verify(source, &envelope).is_err());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`wire`]: crate::wire
+//! [`PflyConnectionBuilder::relay_key`]: crate::PflyConnectionBuilder::relay_key
+//! [`PflyConnectionBuilder::tcp`]: crate::PflyConnectionBuilder::tcp
+//! [`PflyConnectionBuilder::udp`]: crate::PflyConnectionBuilder::udp
+
+use crate::transport::Transport;
+use crate::wire::array;
+use crate::{wire, PflyError, PflyIpcData, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The first bytes of every envelope, `1` being the version of the layout.
+pub const MAGIC: [u8; 4] = *b"PFR1";
+
+/// Size of everything before the frame.
+pub const HEADER_LEN: usize = 24;
+
+/// Size of the signature after the frame.
+pub const TAG_LEN: usize = 32;
+
+/// Longest envelope accepted, one holding a frame with the longest `aircraftType`
+/// [`wire::read_frame`] accepts.
+///
+/// [`wire::read_frame`]: crate::wire::read_frame
+pub const MAX_ENVELOPE_LEN: usize =
+    HEADER_LEN + wire::FIXED_LEN + 8 + wire::MAX_AIRCRAFT_TYPE_LEN + TAG_LEN;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A shared key, kept out of `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Key(Arc<[u8]>);
+
+impl Key {
+    pub(crate) fn new<K: AsRef<[u8]>>(key: K) -> Key {
+        Key(Arc::from(key.as_ref()))
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.0).expect("HMAC takes keys of any length")
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+/// A frame taken out of its envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    /// When the frame was sent, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub sequence: u64,
+    pub frame: PflyIpcData,
+}
+
+/// Puts a frame encoded with [`wire`] into a signed envelope.
+///
+/// [`wire`]: crate::wire
+pub fn seal<K: AsRef<[u8]>>(key: K, frame: &[u8], sequence: u64, sent: SystemTime) -> Vec<u8> {
+    seal_with(&Key::new(key), frame, sequence, sent)
+}
+
+fn seal_with(key: &Key, frame: &[u8], sequence: u64, sent: SystemTime) -> Vec<u8> {
+    let timestamp = sent
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64);
+
+    let mut envelope = Vec::with_capacity(HEADER_LEN + frame.len() + TAG_LEN);
+    envelope.extend_from_slice(&MAGIC);
+    envelope.extend_from_slice(&timestamp.to_le_bytes());
+    envelope.extend_from_slice(&sequence.to_le_bytes());
+    envelope.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+    envelope.extend_from_slice(frame);
+
+    let mut mac = key.mac();
+    mac.update(&envelope);
+    envelope.extend_from_slice(&mac.finalize().into_bytes());
+
+    envelope
+}
+
+/// Checks the signature of exactly one envelope and takes the frame out.
+///
+/// Fails with [`PflyError::Unauthenticated`] if the envelope wasn't signed with `key` or was
+/// changed on the way, and with [`PflyError::MalformedFrame`] if it isn't an envelope at all.
+/// Whether it is recent and not a replay is up to the caller, [`Verifier`] does both.
+pub fn open<K: AsRef<[u8]>>(key: K, envelope: &[u8]) -> Result<Envelope> {
+    open_with(&Key::new(key), envelope)
+}
+
+fn open_with(key: &Key, envelope: &[u8]) -> Result<Envelope> {
+    if envelope_len(envelope) != Some(envelope.len()) {
+        return Err(PflyError::MalformedFrame("not a relay envelope"));
+    }
+
+    let (signed, tag) = envelope.split_at(envelope.len() - TAG_LEN);
+    let mut mac = key.mac();
+    mac.update(signed);
+    mac.verify_slice(tag)
+        .map_err(|_| PflyError::Unauthenticated)?;
+
+    let (frame, len) = wire::decode(&signed[HEADER_LEN..])?;
+    if len != signed.len() - HEADER_LEN {
+        return Err(PflyError::MalformedFrame(
+            "envelope holds more than one frame",
+        ));
+    }
+
+    Ok(Envelope {
+        timestamp: u64::from_le_bytes(array(&signed[4..12])),
+        sequence: u64::from_le_bytes(array(&signed[12..20])),
+        frame,
+    })
+}
+
+/// How long the envelope at the start of `bytes` is, `None` until its header is in there or
+/// if it isn't an envelope.
+pub fn envelope_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC {
+        return None;
+    }
+
+    let len = u32::from_le_bytes(array(&bytes[20..24]));
+    usize::try_from(len)
+        .ok()
+        .map(|len| HEADER_LEN + len + TAG_LEN)
+        .filter(|len| *len <= MAX_ENVELOPE_LEN)
+}
+
+/// Reads the bytes of the next envelope from a stream, to [`open`] or [`Verifier::verify`].
+///
+/// A stream that ends cleanly between two envelopes gives an error of kind `UnexpectedEof`,
+/// one that doesn't start with an envelope or announces more than [`MAX_ENVELOPE_LEN`]
+/// one of kind `InvalidData`.
+pub fn read_envelope<R: Read>(mut reader: R) -> io::Result<Vec<u8>> {
+    let mut envelope = vec![0; HEADER_LEN];
+    reader.read_exact(&mut envelope)?;
+
+    let len = envelope_len(&envelope).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            PflyError::MalformedFrame("not a relay envelope"),
+        )
+    })?;
+    envelope.resize(len, 0);
+    reader.read_exact(&mut envelope[HEADER_LEN..])?;
+
+    Ok(envelope)
+}
+
+/// Signs every payload before handing it to another transport.
+///
+/// Each connection numbers its envelopes starting at 1.
+#[derive(Debug)]
+pub struct SignedTransport {
+    inner: Box<dyn Transport>,
+    signer: Signer,
+}
+
+impl SignedTransport {
+    /// Signs with `key` and sends over `inner`, usually a [`TcpTransport`] or [`UdpTransport`].
+    ///
+    /// [`TcpTransport`]: crate::transport::TcpTransport
+    /// [`UdpTransport`]: crate::transport::UdpTransport
+    pub fn new<T: Transport + 'static, K: AsRef<[u8]>>(inner: T, key: K) -> SignedTransport {
+        SignedTransport::from_box(Box::new(inner), Key::new(key))
+    }
+
+    pub(crate) fn from_box(inner: Box<dyn Transport>, key: Key) -> SignedTransport {
+        SignedTransport {
+            inner,
+            signer: Signer::new(key),
+        }
+    }
+}
+
+impl Transport for SignedTransport {
+    fn send(&mut self, payload: &[u8]) -> Result<()> {
+        let envelope = self.signer.seal(payload);
+        self.inner.send(&envelope)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.inner.close()
+    }
+}
+
+/// Seals payloads with the current time and the next sequence number.
+#[derive(Debug, Clone)]
+pub(crate) struct Signer {
+    key: Key,
+    sequence: u64,
+}
+
+impl Signer {
+    pub(crate) fn new(key: Key) -> Signer {
+        Signer { key, sequence: 0 }
+    }
+
+    pub(crate) fn seal(&mut self, payload: &[u8]) -> Vec<u8> {
+        self.sequence += 1;
+        seal_with(&self.key, payload, self.sequence, SystemTime::now())
+    }
+}
+
+/// Why a [`Verifier`] turned an envelope down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rejection {
+    /// Not an envelope, or one holding a broken frame.
+    Malformed,
+    /// Not signed with the shared key, or changed on the way.
+    Unauthenticated,
+    /// Sent too long ago, or too far in the future, for the clocks to just be a little off.
+    Expired,
+    /// Not newer than the last envelope accepted from the same address.
+    Replayed,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rejection::Malformed => "malformed",
+            Rejection::Unauthenticated => "unauthenticated",
+            Rejection::Expired => "expired",
+            Rejection::Replayed => "replayed",
+        })
+    }
+}
+
+/// Checks envelopes arriving at a relay.
+///
+/// Besides the signature, an envelope has to be recent, sent no more than [`max_skew`]
+/// (30 seconds by default) away from the relay's clock, and newer than the last one accepted
+/// from the same IP address. A captured envelope can therefore only be played again within
+/// that window and from another address, which an allowlist takes care of.
+///
+/// Two bridges on the same host would trip over each other's envelopes, run one per host.
+///
+/// [`max_skew`]: Verifier::max_skew
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    key: Key,
+    max_skew: Duration,
+    last: HashMap<IpAddr, (u64, u64)>,
+}
+
+impl Verifier {
+    pub fn new<K: AsRef<[u8]>>(key: K) -> Verifier {
+        Verifier {
+            key: Key::new(key),
+            max_skew: Duration::from_secs(30),
+            last: HashMap::new(),
+        }
+    }
+
+    /// How far apart the clocks of bridge and relay may be.
+    pub fn max_skew(mut self, max_skew: Duration) -> Verifier {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Checks an envelope that just arrived from `source`.
+    pub fn verify(
+        &mut self,
+        source: IpAddr,
+        envelope: &[u8],
+    ) -> std::result::Result<Envelope, Rejection> {
+        self.verify_at(source, envelope, SystemTime::now())
+    }
+
+    /// Like [`verify`], for an envelope that arrived at `now`.
+    ///
+    /// [`verify`]: Verifier::verify
+    pub fn verify_at(
+        &mut self,
+        source: IpAddr,
+        envelope: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<Envelope, Rejection> {
+        let envelope = open_with(&self.key, envelope).map_err(|err| match err {
+            PflyError::Unauthenticated => Rejection::Unauthenticated,
+            _ => Rejection::Malformed,
+        })?;
+
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        if now.abs_diff(envelope.timestamp) > self.max_skew.as_millis() as u64 {
+            return Err(Rejection::Expired);
+        }
+
+        let order = (envelope.timestamp, envelope.sequence);
+        match self.last.get(&source) {
+            Some(last) if order <= *last => return Err(Rejection::Replayed),
+            Some(_) => {}
+            None => {
+                // Anything that old would be expired anyway, so forgetting it is safe and keeps
+                // addresses that come and go from piling up.
+                let max_skew = self.max_skew.as_millis() as u64;
+                self.last
+                    .retain(|_, (timestamp, _)| now.abs_diff(*timestamp) <= max_skew);
+            }
+        }
+        self.last.insert(source, order);
+
+        Ok(envelope)
+    }
+}
@@ -0,0 +1,241 @@
+//! Plays a [`record`]ing back into projectFly, e.g. to test landing detection without flying.
+//!
+//! Frames are sent with the same spacing they were recorded with, optionally sped up,
+//! starting part way in, or over and over. A [`ReplayControl`] pauses or stops the replay
+//! from another thread.
+//!
+//! ```
+//! use pfly_rust::mock::MockServer;
+//! use pfly_rust::record::Record;
+//! use pfly_rust::replay::Replay;
+//! use pfly_rust::PflyIpcData;
+//! use std::time::Duration;
+//!
+//! let records = (0..3)
+//!     .map(|i| Record { timestamp: i * 1000, frame: PflyIpcData::default() })
+//!     .collect();
+//!
+//! let server = MockServer::start()?;
+//! let mut connection = server.connection_builder().connect()?;
+//!
+//! // Three frames a second apart, played at 20x.
+//! let sent = Replay::new(records).speed(20.0).run(|frame| connection.send(frame))?;
+//! assert_eq!(sent, 3);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`record`]: crate::record
+
+use crate::record::{self, Record};
+use crate::{PflyIpcData, Result};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// A recording ready to be played back.
+#[derive(Debug)]
+pub struct Replay {
+    records: Vec<Record>,
+    speed: f64,
+    seek: Duration,
+    looping: bool,
+    control: ReplayControl,
+}
+
+impl Replay {
+    /// Reads the whole recording at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Replay> {
+        Ok(Replay::new(record::read(path)?.collect::<Result<_>>()?))
+    }
+
+    /// Plays `records`, in order, timed by their timestamps.
+    pub fn new(records: Vec<Record>) -> Replay {
+        Replay {
+            records,
+            speed: 1.0,
+            seek: Duration::from_secs(0),
+            looping: false,
+            control: ReplayControl::default(),
+        }
+    }
+
+    /// Plays `speed` times faster than recorded, 2.0 takes half the time.
+    ///
+    /// # Panics
+    ///
+    /// If `speed` isn't a finite number above zero.
+    pub fn speed(mut self, speed: f64) -> Replay {
+        assert!(
+            speed.is_finite() && speed > 0.0,
+            "replay speed must be above zero"
+        );
+        self.speed = speed;
+        self
+    }
+
+    /// Skips the frames recorded in the first `offset` of the recording.
+    pub fn seek(mut self, offset: Duration) -> Replay {
+        self.seek = offset;
+        self
+    }
+
+    /// Starts over from the seek position after the last frame, until stopped.
+    pub fn looping(mut self, looping: bool) -> Replay {
+        self.looping = looping;
+        self
+    }
+
+    /// How long the recording is from its first to its last frame, at normal speed.
+    pub fn duration(&self) -> Duration {
+        self.records
+            .last()
+            .map(|last| self.offset(last))
+            .unwrap_or_default()
+    }
+
+    /// How many frames the recording holds.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether there's nothing to play.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// A handle to pause or stop the replay while [`run`] is busy.
+    ///
+    /// [`run`]: Replay::run
+    pub fn control(&self) -> ReplayControl {
+        self.control.clone()
+    }
+
+    /// Plays the recording, handing each frame to `send` when it is due.
+    ///
+    /// Returns how many frames were sent once the end is reached or the replay is stopped.
+    /// The first error from `send` ends the replay.
+    pub fn run<F>(&self, mut send: F) -> Result<usize>
+    where
+        F: FnMut(&PflyIpcData) -> Result<()>,
+    {
+        let first = self
+            .records
+            .iter()
+            .position(|record| self.offset(record) >= self.seek);
+        let first = match first {
+            Some(first) => first,
+            None => return Ok(0),
+        };
+
+        let mut sent = 0;
+
+        loop {
+            let mut start = Instant::now();
+
+            for record in &self.records[first..] {
+                // At tiny speeds a frame can be due further out than an `Instant` reaches,
+                // it is then never due and only `stop` ends the wait.
+                let due = self.offset(record).saturating_sub(self.seek);
+                let deadline = Duration::try_from_secs_f64(due.as_secs_f64() / self.speed)
+                    .ok()
+                    .and_then(|due| start.checked_add(due));
+
+                match self.control.wait_until(deadline) {
+                    Some(paused) => start += paused,
+                    None => return Ok(sent),
+                }
+
+                send(&record.frame)?;
+                sent += 1;
+            }
+
+            if !self.looping {
+                return Ok(sent);
+            }
+        }
+    }
+
+    /// How far into the recording `record` is, timestamps going backwards count as no time.
+    fn offset(&self, record: &Record) -> Duration {
+        let first = self.records.first().map_or(0, |first| first.timestamp);
+        Duration::from_millis(record.timestamp.saturating_sub(first))
+    }
+}
+
+/// Pauses, resumes or stops a running [`Replay`] from another thread.
+///
+/// Time spent paused doesn't count, the replay picks up where it left off.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayControl {
+    inner: Arc<(Mutex<ControlState>, Condvar)>,
+}
+
+#[derive(Debug, Default)]
+struct ControlState {
+    paused: bool,
+    stopped: bool,
+}
+
+impl ReplayControl {
+    /// Holds the next frame back until [`resume`] is called.
+    ///
+    /// [`resume`]: ReplayControl::resume
+    pub fn pause(&self) {
+        self.update(|state| state.paused = true);
+    }
+
+    /// Continues after [`pause`].
+    ///
+    /// [`pause`]: ReplayControl::pause
+    pub fn resume(&self) {
+        self.update(|state| state.paused = false);
+    }
+
+    /// Whether the replay is paused right now.
+    pub fn is_paused(&self) -> bool {
+        self.inner.0.lock().unwrap().paused
+    }
+
+    /// Ends the replay before the next frame, for good.
+    pub fn stop(&self) {
+        self.update(|state| state.stopped = true);
+    }
+
+    fn update<F: FnOnce(&mut ControlState)>(&self, change: F) {
+        let (state, changed) = &*self.inner;
+        change(&mut state.lock().unwrap());
+        changed.notify_all();
+    }
+
+    /// Sleeps until `deadline` plus however long the replay was paused on the way.
+    ///
+    /// Returns that paused time, or `None` once stopped. Without a deadline only stopping
+    /// ends the wait.
+    fn wait_until(&self, deadline: Option<Instant>) -> Option<Duration> {
+        let (state, changed) = &*self.inner;
+        let mut state = state.lock().unwrap();
+        let mut paused = Duration::from_secs(0);
+
+        loop {
+            if state.stopped {
+                return None;
+            }
+
+            if state.paused {
+                let paused_at = Instant::now();
+                state = changed
+                    .wait_while(state, |state| state.paused && !state.stopped)
+                    .unwrap();
+                paused += paused_at.elapsed();
+                continue;
+            }
+
+            let now = Instant::now();
+            state = match deadline.and_then(|deadline| deadline.checked_add(paused)) {
+                Some(deadline) if now >= deadline => return Some(paused),
+                Some(deadline) => changed.wait_timeout(state, deadline - now).unwrap().0,
+                None => changed.wait(state).unwrap(),
+            };
+        }
+    }
+}
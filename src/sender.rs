@@ -0,0 +1,253 @@
+use crate::{PflyConnection, PflyError, PflyIpcData, Result};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+/// What a [`SenderHandle`] does with a frame when its queue is already full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Throw away the oldest queued frame to make room, projectFly gets the newest state.
+    #[default]
+    DropOldest,
+    /// Throw away the frame being sent, what is queued goes out first.
+    DropNewest,
+}
+
+/// Sends frames from a thread of its own, so the caller never waits on the socket.
+///
+/// Created with [`PflyConnection::spawn`] or [`PflyConnection::spawn_with`]. The thread owns
+/// the connection and works through a bounded queue, [`send`] only adds to it, which makes it
+/// fine to call from a simulator callback that must not block. Clones all feed the same queue.
+///
+/// Frames refused in strict mode or that could not be recorded are skipped. Any other error
+/// stops the thread, after which [`send`] fails with [`PflyError::Disconnected`] and
+/// [`shutdown`] returns the error. To keep going when projectFly restarts, spawn a
+/// connection per attempt or use a [`ReconnectingConnection`] on a thread of your own.
+///
+/// When the last clone is dropped the queue is sent and the connection closed as if
+/// [`shutdown`] was called, ignoring errors.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn frame() -> pfly_rust::PflyIpcData { unimplemented!() }
+/// let sender = pfly_rust::PflyConnection::connect()?.spawn();
+///
+/// for _ in 0..100 {
+///     // Returns right away, the frame is sent in the background.
+///     sender.send(frame())?;
+/// }
+///
+/// sender.shutdown()?;
+/// # Ok::<(), pfly_rust::PflyError>(())
+/// ```
+///
+/// [`send`]: SenderHandle::send
+/// [`shutdown`]: SenderHandle::shutdown
+/// [`ReconnectingConnection`]: crate::ReconnectingConnection
+#[derive(Clone)]
+pub struct SenderHandle {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    shared: Arc<Shared>,
+    thread: Mutex<Option<JoinHandle<Result<()>>>>,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    /// Signalled when a frame was queued or the thread should finish.
+    ready: Condvar,
+}
+
+struct Queue {
+    frames: VecDeque<PflyIpcData>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: u64,
+    /// No more frames are taken, set by `shutdown` or when the thread stops.
+    closed: bool,
+}
+
+impl SenderHandle {
+    /// How many frames [`PflyConnection::spawn`] queues.
+    pub const DEFAULT_CAPACITY: usize = 8;
+
+    pub(crate) fn spawn(
+        connection: PflyConnection,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> SenderHandle {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                frames: VecDeque::with_capacity(capacity.max(1)),
+                capacity: capacity.max(1),
+                policy,
+                dropped: 0,
+                closed: false,
+            }),
+            ready: Condvar::new(),
+        });
+
+        let thread = {
+            let shared = Arc::clone(&shared);
+            thread::Builder::new()
+                .name("pfly-sender".to_owned())
+                .spawn(move || run(connection, &shared))
+                .expect("could not spawn sender thread")
+        };
+
+        SenderHandle {
+            inner: Arc::new(Inner {
+                shared,
+                thread: Mutex::new(Some(thread)),
+            }),
+        }
+    }
+
+    /// Queues a frame for sending.
+    ///
+    /// With the queue full, a frame is dropped according to the [`OverflowPolicy`], which
+    /// still counts as sent here, see [`dropped`]. Fails with [`PflyError::Disconnected`] once
+    /// the thread stopped.
+    ///
+    /// [`dropped`]: SenderHandle::dropped
+    pub fn send(&self, data: PflyIpcData) -> Result<()> {
+        let mut queue = self.inner.shared.lock();
+        if queue.closed {
+            return Err(PflyError::Disconnected);
+        }
+
+        if queue.frames.len() >= queue.capacity {
+            queue.dropped += 1;
+            match queue.policy {
+                OverflowPolicy::DropOldest => {
+                    queue.frames.pop_front();
+                }
+                OverflowPolicy::DropNewest => return Ok(()),
+            }
+        }
+        queue.frames.push_back(data);
+        drop(queue);
+
+        self.inner.shared.ready.notify_one();
+        Ok(())
+    }
+
+    /// How many frames were dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.inner.shared.lock().dropped
+    }
+
+    /// Whether the thread still takes frames.
+    pub fn is_running(&self) -> bool {
+        !self.inner.shared.lock().closed
+    }
+
+    /// Sends what is still queued, closes the connection and waits for the thread to finish.
+    ///
+    /// Closing also sends the frame a rate limit held back, so projectFly ends up with the
+    /// final state. Returns the error that stopped the thread early, if there was one. Only
+    /// the first call on any of the clones gets it, later ones return `Ok`.
+    pub fn shutdown(&self) -> Result<()> {
+        match self.inner.join() {
+            Some(Ok(result)) => result,
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for SenderHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queue = self.inner.shared.lock();
+        f.debug_struct("SenderHandle")
+            .field("queued", &queue.frames.len())
+            .field("capacity", &queue.capacity)
+            .field("policy", &queue.policy)
+            .field("dropped", &queue.dropped)
+            .field("closed", &queue.closed)
+            .finish()
+    }
+}
+
+impl Inner {
+    /// Closes the queue and joins the thread, `None` if that already happened.
+    fn join(&self) -> Option<thread::Result<Result<()>>> {
+        self.shared.lock().closed = true;
+        self.shared.ready.notify_one();
+
+        let thread = self
+            .thread
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+        thread.map(JoinHandle::join)
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.shared.lock().closed = true;
+            self.shared.ready.notify_one();
+        } else {
+            let _ = self.join();
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        // The queue is consistent between statements, a panic elsewhere doesn't break it.
+        self.queue.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Waits for the next frame, `None` once closed and everything queued was taken.
+    fn next(&self) -> Option<PflyIpcData> {
+        let mut queue = self.lock();
+        loop {
+            if let Some(data) = queue.frames.pop_front() {
+                return Some(data);
+            }
+            if queue.closed {
+                return None;
+            }
+            queue = self
+                .ready
+                .wait(queue)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+
+    /// Takes no more frames, dropping the ones still queued.
+    fn stop(&self) {
+        let mut queue = self.lock();
+        queue.closed = true;
+        queue.frames.clear();
+    }
+}
+
+fn run(mut connection: PflyConnection, shared: &Shared) -> Result<()> {
+    // However the thread ends, even by panicking, senders have to find out.
+    let _stop = StopOnExit(shared);
+
+    while let Some(data) = shared.next() {
+        match connection.send(&data) {
+            Ok(()) | Err(PflyError::Invalid(_)) | Err(PflyError::Record(_)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    connection.close()
+}
+
+struct StopOnExit<'a>(&'a Shared);
+
+impl Drop for StopOnExit<'_> {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
@@ -0,0 +1,531 @@
+//! Generates made up but plausible flights, for exercising projectFly without a simulator.
+//!
+//! A [`Flight`] goes through every phase projectFly cares about: taxi out, takeoff roll,
+//! climb, cruise, descent, a flared landing with the touchdown rate and G load you ask for,
+//! rollout and taxi in. It flies the great circle from departure to arrival, one frame per
+//! [`interval`] of simulated time.
+//!
+//! The small amount of turbulence and frame rate jitter comes from a seeded generator,
+//! so the same flight with the same seed always produces exactly the same frames.
+//!
+//! ```
+//! use pfly_rust::simulate::Flight;
+//! use pfly_rust::units::{Angle, Length, Speed, VerticalSpeed};
+//!
+//! // Toronto to Montreal.
+//! let flight = Flight::new(
+//!     (Angle::Degrees(43.6772), Angle::Degrees(-79.6306)),
+//!     (Angle::Degrees(45.4706), Angle::Degrees(-73.7408)),
+//! )
+//! .cruise_altitude(Length::Feet(24000.0))
+//! .cruise_speed(Speed::Knots(420.0))
+//! .landing_vertical_speed(VerticalSpeed::FeetPerMinute(-160.0))
+//! .touchdown_g_force(1.25)
+//! .aircraft_type("A320")
+//! .seed(7);
+//!
+//! let frames: Vec<_> = flight.frames().collect();
+//!
+//! assert!(frames.iter().any(|frame| frame.altitude == 24000));
+//! let touchdown = frames.iter().find(|frame| frame.landingVerticalSpeed != 0).unwrap();
+//! assert_eq!(touchdown.landingVerticalSpeed, -160);
+//! assert_eq!(touchdown.gForce, 1250);
+//! assert_eq!(frames, flight.frames().collect::<Vec<_>>());
+//! ```
+//!
+//! [`interval`]: Flight::interval
+
+use crate::rng::XorShift;
+use crate::units::{Angle, Length, Speed, VerticalSpeed};
+use crate::PflyIpcData;
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Mean earth radius in nautical miles.
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// Feet per minute in a knot.
+const FEET_PER_MINUTE_PER_KNOT: f64 = 101.269;
+
+const TAXI_SPEED: f64 = 15.0;
+const CLIMB_RATE: f64 = 2000.0;
+const DESCENT_RATE: f64 = 1800.0;
+const MAX_DESCENT_RATE: f64 = 3000.0;
+const FLARE_HEIGHT: f64 = 40.0;
+
+/// Knots per second.
+const TAXI_ACCELERATION: f64 = 2.0;
+const TAKEOFF_ACCELERATION: f64 = 4.0;
+const AIR_ACCELERATION: f64 = 1.0;
+const ROLLOUT_DECELERATION: f64 = 3.0;
+
+/// The heading is held on final and after touchdown instead of pointing at the arrival point.
+const FINAL_DISTANCE: f64 = 2.0;
+
+/// Description of a flight to generate, see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct Flight {
+    departure: (f64, f64),
+    arrival: (f64, f64),
+    departure_elevation: f64,
+    arrival_elevation: f64,
+    cruise_altitude: f64,
+    cruise_speed: f64,
+    landing_vertical_speed: f64,
+    touchdown_g_force: f64,
+    taxi_out: Duration,
+    taxi_in: Duration,
+    interval: Duration,
+    fuel: f64,
+    fuel_burn: f64,
+    magnetic_variation: f64,
+    aircraft_type: Cow<'static, str>,
+    seed: u64,
+}
+
+impl Flight {
+    /// A flight between two `(latitude, longitude)` positions.
+    ///
+    /// It starts out as an airliner at FL350 and 450 knots, landing at -200 fpm and 1.2 G
+    /// from airports at sea level, with a frame every second.
+    pub fn new(departure: (Angle, Angle), arrival: (Angle, Angle)) -> Flight {
+        Flight {
+            departure: (departure.0.degrees(), departure.1.degrees()),
+            arrival: (arrival.0.degrees(), arrival.1.degrees()),
+            departure_elevation: 0.0,
+            arrival_elevation: 0.0,
+            cruise_altitude: 35000.0,
+            cruise_speed: 450.0,
+            landing_vertical_speed: -200.0,
+            touchdown_g_force: 1.2,
+            taxi_out: Duration::from_secs(120),
+            taxi_in: Duration::from_secs(60),
+            interval: Duration::from_secs(1),
+            fuel: 10000.0,
+            fuel_burn: 2500.0,
+            magnetic_variation: 0.0,
+            aircraft_type: Cow::Borrowed(""),
+            seed: 0,
+        }
+    }
+
+    /// Field elevations of the departure and arrival airports.
+    pub fn elevations(mut self, departure: Length, arrival: Length) -> Flight {
+        self.departure_elevation = departure.feet();
+        self.arrival_elevation = arrival.feet();
+        self
+    }
+
+    /// Altitude above mean sea level to level off at, if the flight is long enough to get there.
+    pub fn cruise_altitude(mut self, altitude: Length) -> Flight {
+        self.cruise_altitude = altitude.feet();
+        self
+    }
+
+    /// Ground speed in cruise, takeoff and approach speeds are derived from it.
+    pub fn cruise_speed(mut self, speed: Speed) -> Flight {
+        self.cruise_speed = speed.knots().max(2.0 * TAXI_SPEED);
+        self
+    }
+
+    /// Rate of descent at touchdown, also reported as `landingVerticalSpeed` from then on.
+    ///
+    /// Either sign is accepted, it is always a descent, and at least 1 fpm so the flare
+    /// actually reaches the runway.
+    pub fn landing_vertical_speed(mut self, vertical_speed: VerticalSpeed) -> Flight {
+        self.landing_vertical_speed = (-vertical_speed.feet_per_minute().abs()).min(-1.0);
+        self
+    }
+
+    /// G load of the touchdown frame.
+    pub fn touchdown_g_force(mut self, g_force: f64) -> Flight {
+        self.touchdown_g_force = g_force;
+        self
+    }
+
+    /// How long to taxi before the takeoff roll and after the rollout.
+    pub fn taxi_time(mut self, taxi_out: Duration, taxi_in: Duration) -> Flight {
+        self.taxi_out = taxi_out;
+        self.taxi_in = taxi_in;
+        self
+    }
+
+    /// Simulated time between two frames.
+    pub fn interval(mut self, interval: Duration) -> Flight {
+        self.interval = interval.max(Duration::from_millis(10));
+        self
+    }
+
+    /// Fuel on board at the start and how much is burned per hour in the air, a tenth of that
+    /// on the ground.
+    pub fn fuel(mut self, fuel: f64, burn_per_hour: f64) -> Flight {
+        self.fuel = fuel;
+        self.fuel_burn = burn_per_hour;
+        self
+    }
+
+    /// Difference between true and magnetic north, east is positive.
+    pub fn magnetic_variation(mut self, variation: Angle) -> Flight {
+        self.magnetic_variation = variation.degrees();
+        self
+    }
+
+    pub fn aircraft_type<T: Into<Cow<'static, str>>>(mut self, aircraft_type: T) -> Flight {
+        self.aircraft_type = aircraft_type.into();
+        self
+    }
+
+    /// Seeds the turbulence, flights only differ between seeds in that noise.
+    pub fn seed(mut self, seed: u64) -> Flight {
+        self.seed = seed;
+        self
+    }
+
+    /// The frames of the flight from parked at the departure gate to parked at the arrival.
+    pub fn frames(&self) -> Frames {
+        let (latitude, longitude) = self.departure;
+        let distance = distance(self.departure, self.arrival);
+
+        Frames {
+            rng: XorShift::new(self.seed),
+            phase: Phase::TaxiOut(self.taxi_out.as_secs_f64()),
+            latitude,
+            longitude,
+            altitude: self.departure_elevation,
+            groundspeed: 0.0,
+            vertical_speed: 0.0,
+            heading: bearing(self.departure, self.arrival),
+            to_go: distance,
+            fuel: self.fuel,
+            landing_vertical_speed: 0.0,
+            touchdown: false,
+            flight: self.clone(),
+        }
+    }
+
+    fn rotation_speed(&self) -> f64 {
+        (self.cruise_speed * 0.3).clamp(55.0, 150.0)
+    }
+
+    fn approach_speed(&self) -> f64 {
+        self.rotation_speed() * 1.15
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    /// Seconds of taxiing left.
+    TaxiOut(f64),
+    TakeoffRoll,
+    Climb,
+    Cruise,
+    Descent,
+    Rollout,
+    TaxiIn(f64),
+    Parked,
+    Done,
+}
+
+/// The frames of a [`Flight`], made by [`Flight::frames`].
+#[derive(Debug, Clone)]
+pub struct Frames {
+    flight: Flight,
+    rng: XorShift,
+    phase: Phase,
+    latitude: f64,
+    longitude: f64,
+    /// Feet above mean sea level.
+    altitude: f64,
+    /// Knots, there is no wind so this is also the true airspeed.
+    groundspeed: f64,
+    /// Feet per minute.
+    vertical_speed: f64,
+    /// Degrees true.
+    heading: f64,
+    /// Nautical miles along the route to the touchdown point.
+    to_go: f64,
+    fuel: f64,
+    landing_vertical_speed: f64,
+    /// The next frame is the one touching down.
+    touchdown: bool,
+}
+
+impl Iterator for Frames {
+    type Item = PflyIpcData;
+
+    fn next(&mut self) -> Option<PflyIpcData> {
+        if self.phase == Phase::Done {
+            return None;
+        }
+
+        let frame = self.frame();
+        self.touchdown = false;
+        self.step(self.flight.interval.as_secs_f64());
+
+        Some(frame)
+    }
+}
+
+impl Frames {
+    fn frame(&mut self) -> PflyIpcData {
+        let on_ground = self.on_ground();
+        let agl = self.altitude - self.ground_elevation();
+
+        // Rule of thumb: true airspeed is 2% above indicated per thousand feet.
+        let ias = self.groundspeed / (1.0 + 0.02 * self.altitude.max(0.0) / 1000.0);
+
+        let (pitch, roll, g_force) = if on_ground {
+            (0.0, 0.0, 1.0 + self.noise() * 0.01)
+        } else {
+            let climb_angle = (self.vertical_speed
+                / (self.groundspeed.max(1.0) * FEET_PER_MINUTE_PER_KNOT))
+                .atan()
+                .to_degrees();
+            (
+                climb_angle + 3.0 + self.noise(),
+                self.noise() * 2.0,
+                1.0 + self.noise() * 0.03,
+            )
+        };
+        let g_force = if self.touchdown {
+            self.flight.touchdown_g_force
+        } else {
+            g_force
+        };
+        let fps = 60.0 + self.noise() * 3.0;
+
+        PflyIpcData::builder()
+            .altitude(Length::Feet(self.altitude))
+            .agl(Length::Feet(agl.max(0.0)))
+            .groundspeed(Speed::Knots(self.groundspeed))
+            .ias(Speed::Knots(ias))
+            .heading_true(Angle::Degrees(self.heading))
+            .heading_magnetic(Angle::Degrees(
+                self.heading - self.flight.magnetic_variation,
+            ))
+            .position(
+                Angle::Degrees(self.latitude),
+                Angle::Degrees(self.longitude),
+            )
+            .vertical_speed(VerticalSpeed::FeetPerMinute(self.vertical_speed))
+            .landing_vertical_speed(VerticalSpeed::FeetPerMinute(self.landing_vertical_speed))
+            .g_force(g_force)
+            .fuel(self.fuel.max(0.0).round() as i32)
+            .transponder(2000)
+            .on_ground(on_ground)
+            .pitch(Angle::Degrees(pitch))
+            .roll(Angle::Degrees(roll))
+            .fps(fps)
+            .aircraft_type(self.flight.aircraft_type.clone())
+            .build()
+    }
+
+    fn step(&mut self, dt: f64) {
+        let rotation_speed = self.flight.rotation_speed();
+        let approach_speed = self.flight.approach_speed();
+        let cruise_speed = self.flight.cruise_speed;
+        let cruise_altitude = self.flight.cruise_altitude;
+        let departure_elevation = self.flight.departure_elevation;
+        let arrival_elevation = self.flight.arrival_elevation;
+        let landing_vertical_speed = self.flight.landing_vertical_speed;
+
+        match self.phase {
+            Phase::TaxiOut(left) => {
+                self.accelerate(TAXI_SPEED, TAXI_ACCELERATION, dt);
+                self.phase = if left <= dt {
+                    Phase::TakeoffRoll
+                } else {
+                    Phase::TaxiOut(left - dt)
+                };
+            }
+            Phase::TakeoffRoll => {
+                self.accelerate(rotation_speed, TAKEOFF_ACCELERATION, dt);
+                if self.groundspeed >= rotation_speed {
+                    self.phase = Phase::Climb;
+                }
+            }
+            Phase::Climb => {
+                self.accelerate(cruise_speed, AIR_ACCELERATION, dt);
+                self.approach_vertical_speed(CLIMB_RATE, dt);
+
+                if self.altitude >= cruise_altitude {
+                    self.altitude = cruise_altitude;
+                    self.vertical_speed = 0.0;
+                    self.phase = Phase::Cruise;
+                }
+                if self.altitude - departure_elevation > 1000.0 && self.top_of_descent() {
+                    self.phase = Phase::Descent;
+                }
+            }
+            Phase::Cruise => {
+                self.accelerate(cruise_speed, AIR_ACCELERATION, dt);
+                if self.top_of_descent() {
+                    self.phase = Phase::Descent;
+                }
+            }
+            Phase::Descent => {
+                // Slow down to the approach speed over the last 40 miles.
+                let slowdown = ((self.to_go - 8.0) / 40.0).clamp(0.0, 1.0);
+                self.accelerate(
+                    approach_speed + (cruise_speed - approach_speed) * slowdown,
+                    AIR_ACCELERATION,
+                    dt,
+                );
+
+                let agl = self.altitude - arrival_elevation;
+                if agl > FLARE_HEIGHT {
+                    // Aim straight for the touchdown point.
+                    let minutes_to_go = self.to_go.max(0.0) / self.groundspeed * 60.0;
+                    let target = if minutes_to_go > 0.0 {
+                        (-(agl - FLARE_HEIGHT) / minutes_to_go).max(-MAX_DESCENT_RATE)
+                    } else {
+                        -MAX_DESCENT_RATE
+                    };
+                    self.approach_vertical_speed(target.min(-300.0), dt);
+                } else {
+                    // Flare, easing into the touchdown rate.
+                    let flare = (agl / FLARE_HEIGHT).max(0.0);
+                    let target = landing_vertical_speed
+                        + (self.vertical_speed.min(0.0) - landing_vertical_speed) * flare;
+                    self.vertical_speed = target.min(landing_vertical_speed);
+                }
+
+                if self.altitude + self.vertical_speed * dt / 60.0 <= arrival_elevation {
+                    self.vertical_speed = landing_vertical_speed;
+                    self.landing_vertical_speed = landing_vertical_speed;
+                    self.altitude = arrival_elevation;
+                    self.touchdown = true;
+                    self.phase = Phase::Rollout;
+                }
+            }
+            Phase::Rollout => {
+                self.vertical_speed = 0.0;
+                self.accelerate(TAXI_SPEED, ROLLOUT_DECELERATION, dt);
+                if self.groundspeed <= TAXI_SPEED {
+                    self.phase = Phase::TaxiIn(self.flight.taxi_in.as_secs_f64());
+                }
+            }
+            Phase::TaxiIn(left) => {
+                self.phase = if left <= dt {
+                    self.groundspeed = 0.0;
+                    Phase::Parked
+                } else {
+                    Phase::TaxiIn(left - dt)
+                };
+            }
+            Phase::Parked | Phase::Done => self.phase = Phase::Done,
+        }
+
+        if !self.touchdown {
+            self.altitude += self.vertical_speed * dt / 60.0;
+        }
+        self.travel(dt);
+
+        let burn = if self.on_ground() { 0.1 } else { 1.0 } * self.flight.fuel_burn;
+        self.fuel -= burn * dt / 3600.0;
+    }
+
+    fn on_ground(&self) -> bool {
+        match self.phase {
+            Phase::TaxiOut(_) | Phase::TakeoffRoll | Phase::TaxiIn(_) | Phase::Parked => true,
+            Phase::Rollout | Phase::Done => true,
+            Phase::Climb | Phase::Cruise | Phase::Descent => false,
+        }
+    }
+
+    fn ground_elevation(&self) -> f64 {
+        match self.phase {
+            Phase::TaxiOut(_) | Phase::TakeoffRoll | Phase::Climb => {
+                self.flight.departure_elevation
+            }
+            _ => self.flight.arrival_elevation,
+        }
+    }
+
+    /// Whether it's time to start down, leaving a few miles to slow down on the way.
+    fn top_of_descent(&self) -> bool {
+        let agl = self.altitude - self.flight.arrival_elevation;
+        let average_speed = (self.groundspeed + self.flight.approach_speed()) / 2.0;
+        let needed = agl / DESCENT_RATE / 60.0 * average_speed + 5.0;
+
+        self.to_go <= needed
+    }
+
+    fn accelerate(&mut self, target: f64, rate: f64, dt: f64) {
+        let change = rate * dt;
+        self.groundspeed = if self.groundspeed < target {
+            (self.groundspeed + change).min(target)
+        } else {
+            (self.groundspeed - change).max(target)
+        };
+    }
+
+    /// Eases the vertical speed towards `target` over a few seconds.
+    fn approach_vertical_speed(&mut self, target: f64, dt: f64) {
+        self.vertical_speed += (target - self.vertical_speed) * (dt / 5.0).min(1.0);
+    }
+
+    fn travel(&mut self, dt: f64) {
+        let distance = self.groundspeed * dt / 3600.0;
+
+        if self.to_go > FINAL_DISTANCE && !self.on_ground_after_landing() {
+            self.heading = bearing((self.latitude, self.longitude), self.flight.arrival);
+        }
+
+        let (latitude, longitude) =
+            destination((self.latitude, self.longitude), self.heading, distance);
+        self.latitude = latitude;
+        self.longitude = longitude;
+        self.to_go -= distance;
+    }
+
+    fn on_ground_after_landing(&self) -> bool {
+        matches!(
+            self.phase,
+            Phase::Rollout | Phase::TaxiIn(_) | Phase::Parked | Phase::Done
+        )
+    }
+
+    /// Uniform in `[-1, 1)`.
+    fn noise(&mut self) -> f64 {
+        self.rng.next_f64() * 2.0 - 1.0
+    }
+}
+
+/// Great circle distance in nautical miles.
+fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_NM * a.sqrt().min(1.0).asin()
+}
+
+/// Initial great circle course in degrees true.
+fn bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+
+    let y = (lon2 - lon1).sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * (lon2 - lon1).cos();
+
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Where `distance` nautical miles along `course` from `from` ends up.
+fn destination(from: (f64, f64), course: f64, distance: f64) -> (f64, f64) {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let course = course.to_radians();
+    let angle = distance / EARTH_RADIUS_NM;
+
+    let lat2 = (lat1.sin() * angle.cos() + lat1.cos() * angle.sin() * course.cos()).asin();
+    let lon2 = lon1
+        + (course.sin() * angle.sin() * lat1.cos()).atan2(angle.cos() - lat1.sin() * lat2.sin());
+
+    (
+        lat2.to_degrees(),
+        (lon2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0,
+    )
+}
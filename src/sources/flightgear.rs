@@ -0,0 +1,142 @@
+//! Reads FlightGear through its generic protocol.
+//!
+//! FlightGear can write any set of properties as CSV lines over UDP, described by an XML
+//! protocol file. The one this source expects ships with the crate as [`PROTOCOL_XML`],
+//! save it as `$FG_ROOT/Protocol/pfly.xml` and start FlightGear with
+//! `--generic=socket,out,10,127.0.0.1,5500,udp,pfly`.
+//!
+//! ```no_run
+//! use pfly_rust::sources::flightgear::FlightGearSource;
+//! use pfly_rust::sources::Source;
+//!
+//! let mut flightgear = FlightGearSource::bind("127.0.0.1:5500")?;
+//! let frame = flightgear.recv_frame()?;
+//! # Ok::<(), pfly_rust::PflyError>(())
+//! ```
+
+use crate::sources::{Field, Source};
+use crate::units::KILOGRAMS_PER_POUND;
+use crate::{BridgeType, PflyError, PflyIpcData, Result};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// The FlightGear protocol file matching [`parse_line`].
+pub const PROTOCOL_XML: &str = include_str!("../../protocol/flightgear/pfly.xml");
+
+/// Numeric columns in protocol order, with the factor to projectFly's unit.
+const COLUMNS: &[(Field, f64)] = &[
+    (Field::Altitude, 1.0),
+    (Field::Agl, 1.0),
+    (Field::Groundspeed, 1.0),
+    (Field::Ias, 1.0),
+    (Field::HeadingTrue, 1.0),
+    (Field::HeadingMagnetic, 1.0),
+    (Field::Latitude, 1.0),
+    (Field::Longitude, 1.0),
+    // Feet per second to feet per minute.
+    (Field::VerticalSpeed, 60.0),
+    (Field::GForce, 1.0),
+    (Field::Fuel, KILOGRAMS_PER_POUND),
+    (Field::Transponder, 1.0),
+    (Field::OnGround, 1.0),
+    (Field::Paused, 1.0),
+    (Field::Pitch, 1.0),
+    (Field::Roll, 1.0),
+    (Field::Fps, 1.0),
+];
+
+/// Parses one line written with [`PROTOCOL_XML`].
+///
+/// The last column is the FlightGear aircraft name (e.g. `c172p`), reported as the aircraft type.
+/// projectFly only knows about MSFS, FSX, Infinite Flight and X-Plane bridges and FlightGear
+/// frames come in over the X-Plane socket, so the bridge type is [`BridgeType::XPlane`].
+///
+/// # Example
+///
+/// ```
+/// use pfly_rust::sources::flightgear::parse_line;
+///
+/// let frame = parse_line(
+///     "569.0,0.0,0.0,0.0,237.1,247.3,43.6772222,-79.6305556,0.00,1.000,300.0,1200,1,0,0.5,0.0,60,c172p\n",
+/// )?;
+/// assert_eq!(frame.altitude, 569);
+/// assert_eq!(frame.fuel, 136);
+/// assert!(frame.isOnGround);
+/// assert_eq!(frame.aircraftType, "c172p");
+/// # Ok::<(), pfly_rust::PflyError>(())
+/// ```
+pub fn parse_line(line: &str) -> Result<PflyIpcData> {
+    let mut columns = line.trim_end_matches(&['\r', '\n'][..]).split(',');
+    let mut data = PflyIpcData {
+        bridgeType: BridgeType::XPlane,
+        ..PflyIpcData::default()
+    };
+
+    for (field, factor) in COLUMNS {
+        let value: f64 = columns
+            .next()
+            .ok_or(PflyError::MalformedPacket("too few FlightGear columns"))?
+            .trim()
+            .parse()
+            .map_err(|_| PflyError::MalformedPacket("FlightGear column is not a number"))?;
+
+        field.apply(&mut data, value * factor);
+    }
+
+    // Whatever is left is the aircraft name, which could in theory contain the separator.
+    let aircraft_type = columns.collect::<Vec<_>>().join(",");
+    data.aircraftType = aircraft_type.trim().to_owned().into();
+
+    Ok(data)
+}
+
+/// Receives FlightGear generic protocol lines over UDP.
+#[derive(Debug)]
+pub struct FlightGearSource {
+    socket: UdpSocket,
+}
+
+impl FlightGearSource {
+    /// The port used in the examples and the protocol file.
+    pub const DEFAULT_PORT: u16 = 5500;
+
+    /// Listens on `addr`, the address FlightGear's `--generic` option sends to.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<FlightGearSource> {
+        let socket = UdpSocket::bind(addr).map_err(PflyError::Socket)?;
+
+        Ok(FlightGearSource { socket })
+    }
+
+    /// The address the source is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.socket.local_addr().map_err(PflyError::Socket)
+    }
+
+    /// Makes [`recv_frame`] give up after `timeout` instead of waiting forever.
+    ///
+    /// [`recv_frame`]: Source::recv_frame
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        self.socket
+            .set_read_timeout(timeout)
+            .map_err(PflyError::Socket)
+    }
+}
+
+impl Source for FlightGearSource {
+    /// Returns the newest line of the next datagram, FlightGear sends one line per datagram.
+    fn recv_frame(&mut self) -> Result<PflyIpcData> {
+        let mut buffer = [0u8; 2048];
+
+        let len = self.socket.recv(&mut buffer).map_err(PflyError::Source)?;
+        let text = std::str::from_utf8(&buffer[..len])
+            .map_err(|_| PflyError::MalformedPacket("FlightGear line is not UTF-8"))?;
+
+        let line = text
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .ok_or(PflyError::MalformedPacket("empty FlightGear datagram"))?;
+
+        parse_line(line)
+    }
+}
@@ -0,0 +1,87 @@
+//! Readers that turn simulator output into [`PflyIpcData`] frames.
+//!
+//! Each source implements [`Source`], so a bridge loop doesn't need to care which
+//! simulator the frames come from.
+//!
+//! [`PflyIpcData`]: crate::PflyIpcData
+
+pub mod flightgear;
+pub mod xplane;
+
+use crate::builder::{heading_degrees, round};
+use crate::units::Angle;
+use crate::{PflyIpcData, Result};
+
+/// Something that produces frames for projectFly.
+pub trait Source {
+    /// Blocks until the next complete frame is available.
+    ///
+    /// A read timeout set on the source shows up as a [`PflyError::Source`] error of kind
+    /// `WouldBlock` or `TimedOut`.
+    ///
+    /// [`PflyError::Source`]: crate::PflyError::Source
+    fn recv_frame(&mut self) -> Result<PflyIpcData>;
+
+    /// Starts over after the simulator went quiet, e.g. because it was restarted.
+    ///
+    /// Values from before are forgotten and sources that have to ask for data ask again.
+    /// Does nothing by default.
+    fn reset(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`PflyIpcData`] field that can be filled in from a single number.
+///
+/// [`PflyIpcData`]: crate::PflyIpcData
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    Altitude,
+    Agl,
+    Groundspeed,
+    Ias,
+    HeadingTrue,
+    HeadingMagnetic,
+    Latitude,
+    Longitude,
+    VerticalSpeed,
+    GForce,
+    Fuel,
+    Transponder,
+    OnGround,
+    Slew,
+    Paused,
+    Pitch,
+    Roll,
+    Fps,
+}
+
+impl Field {
+    /// Stores `value` into `data`.
+    ///
+    /// `value` must already be in projectFly's unit for the field: feet, knots, feet per minute,
+    /// degrees or G. Headings are wrapped into `0..360`, G is scaled by 1000 and flags are set
+    /// when `value` is above `0.5`.
+    pub fn apply(self, data: &mut PflyIpcData, value: f64) {
+        match self {
+            Field::Altitude => data.altitude = round(value),
+            Field::Agl => data.agl = round(value),
+            Field::Groundspeed => data.groundspeed = round(value),
+            Field::Ias => data.ias = round(value),
+            Field::HeadingTrue => data.headingTrue = heading_degrees(Angle::Degrees(value)),
+            Field::HeadingMagnetic => data.headingMagnetic = heading_degrees(Angle::Degrees(value)),
+            Field::Latitude => data.latitude = value,
+            Field::Longitude => data.longitude = value,
+            Field::VerticalSpeed => data.verticalSpeed = round(value),
+            Field::GForce => data.gForce = round(value * 1000.0),
+            Field::Fuel => data.fuel = round(value),
+            Field::Transponder => data.transponder = round(value),
+            Field::OnGround => data.isOnGround = value > 0.5,
+            Field::Slew => data.isSlew = value > 0.5,
+            Field::Paused => data.isPaused = value > 0.5,
+            Field::Pitch => data.pitch = round(value),
+            Field::Roll => data.roll = round(value),
+            Field::Fps => data.fps = round(value),
+        }
+    }
+}
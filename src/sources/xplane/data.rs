@@ -0,0 +1,273 @@
+//! Reads X-Plane's legacy "Data Output" `DATA` packets.
+//!
+//! Instead of answering subscriptions, X-Plane can be set up to send selected data sets to
+//! a UDP address on its own (Settings → Data Output → "Send network data output").
+//! Each packet is `DATA` plus one internal byte, followed by any number of 36 byte records:
+//! an `i32` data set index and eight `f32` values, all little endian.
+//!
+//! [`DataMapping::default`] follows the X-Plane 11 and 12 data set layout, so enabling data sets
+//! 0, 3, 4, 17, 20 and 63 for network output is all that's needed.
+//!
+//! ```no_run
+//! use pfly_rust::sources::xplane::data::{DataListener, DataMapping};
+//! use pfly_rust::sources::Source;
+//!
+//! let mut xplane = DataListener::bind("0.0.0.0:49003", DataMapping::default())?
+//!     .aircraft_type("C172");
+//!
+//! let frame = xplane.recv_frame()?;
+//! # Ok::<(), pfly_rust::PflyError>(())
+//! ```
+
+use crate::sources::{Field, Source};
+use crate::units::KILOGRAMS_PER_POUND;
+use crate::{PflyError, PflyIpcData, Result};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryInto;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// Size of one record: the index followed by eight values.
+const RECORD_LEN: usize = 4 + 8 * 4;
+
+/// X-Plane fills slots a data set doesn't use with this.
+const UNUSED_SLOT: f32 = -999.0;
+
+/// One data set out of a `DATA` packet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataRecord {
+    pub index: u32,
+    pub values: [f32; 8],
+}
+
+/// Splits a `DATA` packet into its records.
+///
+/// # Example
+///
+/// ```
+/// use pfly_rust::sources::xplane::data::parse_data;
+///
+/// let mut packet = b"DATA*".to_vec();
+/// packet.extend_from_slice(&3i32.to_le_bytes());
+/// for value in &[151.5f32, 150.0, 160.2, 149.8, -999.0, 184.4, 172.9, 172.4] {
+///     packet.extend_from_slice(&value.to_le_bytes());
+/// }
+///
+/// let records = parse_data(&packet)?;
+/// assert_eq!(records[0].index, 3);
+/// assert_eq!(records[0].values[0], 151.5);
+/// # Ok::<(), pfly_rust::PflyError>(())
+/// ```
+pub fn parse_data(packet: &[u8]) -> Result<Vec<DataRecord>> {
+    if packet.len() < 5 || &packet[..4] != b"DATA" {
+        return Err(PflyError::MalformedPacket("not a DATA packet"));
+    }
+
+    let records = packet[5..].chunks_exact(RECORD_LEN);
+    if !records.remainder().is_empty() {
+        return Err(PflyError::MalformedPacket("truncated DATA record"));
+    }
+
+    records
+        .map(|record| {
+            let index = i32::from_le_bytes(record[..4].try_into().unwrap());
+            let index = index
+                .try_into()
+                .map_err(|_| PflyError::MalformedPacket("negative DATA index"))?;
+
+            let mut values = [0f32; 8];
+            for (value, bytes) in values.iter_mut().zip(record[4..].chunks_exact(4)) {
+                *value = f32::from_le_bytes(bytes.try_into().unwrap());
+            }
+
+            Ok(DataRecord { index, values })
+        })
+        .collect()
+}
+
+/// Where a field is found in the data sets: which index, which of the eight slots,
+/// and what to multiply it by to get projectFly's unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataSlot {
+    pub index: u32,
+    pub slot: usize,
+    pub factor: f64,
+}
+
+/// Which data set slot feeds which [`Field`].
+///
+/// The default follows X-Plane 11/12. Fields without a slot keep their default value,
+/// X-Plane doesn't output the transponder, pause or slew state as data sets.
+///
+/// # Example
+///
+/// ```
+/// use pfly_rust::sources::xplane::data::DataMapping;
+/// use pfly_rust::sources::Field;
+///
+/// // Take the altitude from the indicated altitude slot instead.
+/// let mapping = DataMapping::default().set(Field::Altitude, 20, 5, 1.0);
+/// assert!(mapping.required_indices().contains(&20));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataMapping {
+    slots: HashMap<Field, DataSlot>,
+}
+
+impl DataMapping {
+    /// A mapping without any fields, to build a custom one from scratch.
+    pub fn empty() -> DataMapping {
+        DataMapping {
+            slots: HashMap::new(),
+        }
+    }
+
+    /// Reads `field` from `slot` (0 to 7) of data set `index`, multiplied by `factor`.
+    pub fn set(mut self, field: Field, index: u32, slot: usize, factor: f64) -> DataMapping {
+        self.slots.insert(
+            field,
+            DataSlot {
+                index,
+                slot: slot.min(7),
+                factor,
+            },
+        );
+        self
+    }
+
+    /// Stops filling in `field`.
+    pub fn remove(mut self, field: Field) -> DataMapping {
+        self.slots.remove(&field);
+        self
+    }
+
+    /// Where `field` is read from, if anywhere.
+    pub fn slot(&self, field: Field) -> Option<DataSlot> {
+        self.slots.get(&field).copied()
+    }
+
+    /// The data set indices that must be seen before a frame is complete.
+    pub fn required_indices(&self) -> BTreeSet<u32> {
+        self.slots.values().map(|slot| slot.index).collect()
+    }
+}
+
+impl Default for DataMapping {
+    fn default() -> DataMapping {
+        DataMapping::empty()
+            // 0: frame rate
+            .set(Field::Fps, 0, 0, 1.0)
+            // 3: speeds, knots
+            .set(Field::Ias, 3, 0, 1.0)
+            .set(Field::Groundspeed, 3, 3, 1.0)
+            // 4: Mach, VVI, g-load
+            .set(Field::VerticalSpeed, 4, 2, 1.0)
+            .set(Field::GForce, 4, 4, 1.0)
+            // 17: pitch, roll, headings
+            .set(Field::Pitch, 17, 0, 1.0)
+            .set(Field::Roll, 17, 1, 1.0)
+            .set(Field::HeadingTrue, 17, 2, 1.0)
+            .set(Field::HeadingMagnetic, 17, 3, 1.0)
+            // 20: latitude, longitude, altitude
+            .set(Field::Latitude, 20, 0, 1.0)
+            .set(Field::Longitude, 20, 1, 1.0)
+            .set(Field::Altitude, 20, 2, 1.0)
+            .set(Field::Agl, 20, 3, 1.0)
+            .set(Field::OnGround, 20, 4, 1.0)
+            // 63: payload weights, fuel in pounds converted to kilograms like the RREF source
+            .set(Field::Fuel, 63, 2, KILOGRAMS_PER_POUND)
+    }
+}
+
+/// Listens for `DATA` packets and turns them into frames.
+///
+/// Values are kept between packets, so data sets split over several packets are fine.
+/// Frames are only produced once every data set the [`DataMapping`] needs has arrived.
+#[derive(Debug)]
+pub struct DataListener {
+    socket: UdpSocket,
+    mapping: DataMapping,
+    records: HashMap<u32, [f32; 8]>,
+    aircraft_type: Cow<'static, str>,
+}
+
+impl DataListener {
+    /// Listens on `addr`, the address X-Plane was told to send data output to.
+    pub fn bind<A: ToSocketAddrs>(addr: A, mapping: DataMapping) -> Result<DataListener> {
+        let socket = UdpSocket::bind(addr).map_err(PflyError::Socket)?;
+
+        Ok(DataListener {
+            socket,
+            mapping,
+            records: HashMap::new(),
+            aircraft_type: Cow::Borrowed(""),
+        })
+    }
+
+    /// Sets the aircraft type to report, `DATA` packets don't carry it.
+    pub fn aircraft_type<T: Into<Cow<'static, str>>>(mut self, aircraft_type: T) -> DataListener {
+        self.aircraft_type = aircraft_type.into();
+        self
+    }
+
+    /// The address the listener is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.socket.local_addr().map_err(PflyError::Socket)
+    }
+
+    /// Makes [`recv_frame`] give up after `timeout` instead of waiting forever.
+    ///
+    /// [`recv_frame`]: Source::recv_frame
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        self.socket
+            .set_read_timeout(timeout)
+            .map_err(PflyError::Socket)
+    }
+
+    /// Feeds a packet received some other way, e.g. from a socket shared with other tools.
+    pub fn update(&mut self, packet: &[u8]) -> Result<()> {
+        for record in parse_data(packet)? {
+            let values = self.records.entry(record.index).or_insert(record.values);
+
+            for (value, new) in values.iter_mut().zip(record.values.iter()) {
+                if *new != UNUSED_SLOT {
+                    *value = *new;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The latest frame, once every required data set has been received.
+    pub fn frame(&self) -> Option<PflyIpcData> {
+        let mut data = PflyIpcData {
+            aircraftType: self.aircraft_type.clone(),
+            ..PflyIpcData::default()
+        };
+
+        for (field, slot) in &self.mapping.slots {
+            let value = self.records.get(&slot.index)?[slot.slot];
+            field.apply(&mut data, f64::from(value) * slot.factor);
+        }
+
+        Some(data)
+    }
+}
+
+impl Source for DataListener {
+    fn recv_frame(&mut self) -> Result<PflyIpcData> {
+        let mut buffer = [0u8; 4096];
+
+        loop {
+            let len = self.socket.recv(&mut buffer).map_err(PflyError::Source)?;
+
+            self.update(&buffer[..len])?;
+
+            if let Some(frame) = self.frame() {
+                return Ok(frame);
+            }
+        }
+    }
+}
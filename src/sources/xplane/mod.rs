@@ -0,0 +1,310 @@
+//! Reads X-Plane over its UDP interface.
+//!
+//! [`RrefSource`] subscribes to the datarefs it needs with `RREF` requests on X-Plane's
+//! UDP port (49000 by default) and X-Plane then streams the values back at the requested rate.
+//! No plugin is needed on the X-Plane side.
+//!
+//! For setups that broadcast "Data Output" packets instead, see [`data::DataListener`].
+//!
+//! ```no_run
+//! use pfly_rust::sources::xplane::RrefSource;
+//! use pfly_rust::sources::Source;
+//! use pfly_rust::PflyConnection;
+//!
+//! let mut xplane = RrefSource::connect(("127.0.0.1", RrefSource::DEFAULT_PORT), 10)?;
+//! let mut connection = PflyConnection::connect()?;
+//!
+//! loop {
+//!     connection.send(&xplane.recv_frame()?)?;
+//! }
+//! # Ok::<(), pfly_rust::PflyError>(())
+//! ```
+
+pub mod data;
+
+use crate::sources::{Field, Source};
+use crate::units::{FEET_PER_METER, KNOTS_PER_METER_PER_SECOND};
+use crate::{PflyError, PflyIpcData, Result};
+use std::convert::{TryFrom, TryInto};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// Length of the dataref path in an `RREF` request, padded with zeroes.
+const RREF_PATH_LEN: usize = 400;
+
+/// How a dataref's value ends up in the frame.
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    /// Multiply by the factor and store into the field.
+    Field(Field, f64),
+    /// Seconds per frame, stored as frames per second.
+    FramePeriod,
+    /// One byte of the ICAO type designator.
+    AircraftType(usize),
+}
+
+/// The datarefs subscribed to, the position in this list is the `RREF` index.
+const DATAREFS: &[(&str, Target)] = &[
+    (
+        "sim/flightmodel/position/elevation",
+        Target::Field(Field::Altitude, FEET_PER_METER),
+    ),
+    (
+        "sim/flightmodel/position/y_agl",
+        Target::Field(Field::Agl, FEET_PER_METER),
+    ),
+    (
+        "sim/flightmodel/position/groundspeed",
+        Target::Field(Field::Groundspeed, KNOTS_PER_METER_PER_SECOND),
+    ),
+    (
+        "sim/cockpit2/gauges/indicators/airspeed_kts_pilot",
+        Target::Field(Field::Ias, 1.0),
+    ),
+    (
+        "sim/flightmodel/position/true_psi",
+        Target::Field(Field::HeadingTrue, 1.0),
+    ),
+    (
+        "sim/flightmodel/position/mag_psi",
+        Target::Field(Field::HeadingMagnetic, 1.0),
+    ),
+    (
+        "sim/flightmodel/position/latitude",
+        Target::Field(Field::Latitude, 1.0),
+    ),
+    (
+        "sim/flightmodel/position/longitude",
+        Target::Field(Field::Longitude, 1.0),
+    ),
+    (
+        "sim/flightmodel/position/vh_ind_fpm",
+        Target::Field(Field::VerticalSpeed, 1.0),
+    ),
+    (
+        "sim/flightmodel/forces/g_nrml",
+        Target::Field(Field::GForce, 1.0),
+    ),
+    (
+        "sim/flightmodel/weight/m_fuel_total",
+        Target::Field(Field::Fuel, 1.0),
+    ),
+    (
+        "sim/cockpit/radios/transponder_code",
+        Target::Field(Field::Transponder, 1.0),
+    ),
+    (
+        "sim/flightmodel/failures/onground_any",
+        Target::Field(Field::OnGround, 1.0),
+    ),
+    (
+        "sim/operation/override/override_planepath[0]",
+        Target::Field(Field::Slew, 1.0),
+    ),
+    ("sim/time/paused", Target::Field(Field::Paused, 1.0)),
+    (
+        "sim/flightmodel/position/theta",
+        Target::Field(Field::Pitch, 1.0),
+    ),
+    (
+        "sim/flightmodel/position/phi",
+        Target::Field(Field::Roll, 1.0),
+    ),
+    ("sim/operation/misc/frame_rate_period", Target::FramePeriod),
+    ("sim/aircraft/view/acf_ICAO[0]", Target::AircraftType(0)),
+    ("sim/aircraft/view/acf_ICAO[1]", Target::AircraftType(1)),
+    ("sim/aircraft/view/acf_ICAO[2]", Target::AircraftType(2)),
+    ("sim/aircraft/view/acf_ICAO[3]", Target::AircraftType(3)),
+];
+
+/// Receives frames from X-Plane through `RREF` dataref subscriptions.
+///
+/// Altitudes and speeds are converted from X-Plane's metric datarefs, fuel is passed on in
+/// kilograms, and the aircraft type comes from the ICAO code of the loaded aircraft.
+/// `landingVerticalSpeed` and `time` are left at zero, [`LandingDetector`] can fill in the former.
+///
+/// X-Plane forgets the subscriptions when it restarts, [`resubscribe`] asks again. The
+/// subscriptions are cancelled when the source is dropped.
+///
+/// [`resubscribe`]: RrefSource::resubscribe
+///
+/// [`LandingDetector`]: crate::landing::LandingDetector
+#[derive(Debug)]
+pub struct RrefSource {
+    socket: UdpSocket,
+    xplane: SocketAddr,
+    frequency: i32,
+    values: Vec<Option<f32>>,
+}
+
+impl RrefSource {
+    /// The port X-Plane listens on for UDP requests.
+    pub const DEFAULT_PORT: u16 = 49000;
+
+    /// Subscribes to X-Plane at `xplane`, asking for `frequency` updates per second.
+    pub fn connect<A: ToSocketAddrs>(xplane: A, frequency: u32) -> Result<RrefSource> {
+        let xplane = xplane
+            .to_socket_addrs()
+            .map_err(PflyError::Address)?
+            .next()
+            .ok_or_else(|| {
+                PflyError::Address(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no address to reach X-Plane at",
+                ))
+            })?;
+
+        let local: SocketAddr = if xplane.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local).map_err(PflyError::Socket)?;
+
+        let source = RrefSource {
+            socket,
+            xplane,
+            frequency: frequency.min(i32::MAX as u32) as i32,
+            values: vec![None; DATAREFS.len()],
+        };
+        source.subscribe(source.frequency)?;
+
+        Ok(source)
+    }
+
+    /// Makes [`recv_frame`] give up after `timeout` instead of waiting forever.
+    ///
+    /// [`recv_frame`]: Source::recv_frame
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        self.socket
+            .set_read_timeout(timeout)
+            .map_err(PflyError::Socket)
+    }
+
+    /// The latest frame, once every dataref has been received at least once.
+    pub fn frame(&self) -> Option<PflyIpcData> {
+        let mut data = PflyIpcData::default();
+        let mut aircraft_type = [0u8; 4];
+
+        for ((_, target), value) in DATAREFS.iter().zip(&self.values) {
+            let value = f64::from((*value)?);
+
+            match *target {
+                Target::Field(field, factor) => field.apply(&mut data, value * factor),
+                Target::FramePeriod if value > 0.0 => Field::Fps.apply(&mut data, 1.0 / value),
+                Target::FramePeriod => {}
+                Target::AircraftType(i) => aircraft_type[i] = value as u8,
+            }
+        }
+
+        data.aircraftType = String::from_utf8_lossy(&aircraft_type)
+            .trim_end_matches('\0')
+            .trim()
+            .to_owned()
+            .into();
+
+        Some(data)
+    }
+
+    /// Sends the subscriptions again and forgets the values received so far.
+    ///
+    /// For when X-Plane wasn't running yet or restarted, [`Source::reset`] calls this.
+    pub fn resubscribe(&mut self) -> Result<()> {
+        self.values.iter_mut().for_each(|value| *value = None);
+        self.subscribe(self.frequency)
+    }
+
+    fn subscribe(&self, frequency: i32) -> Result<()> {
+        for (index, (path, _)) in DATAREFS.iter().enumerate() {
+            let request = rref_request(frequency, index as i32, path);
+
+            self.socket
+                .send_to(&request, self.xplane)
+                .map_err(PflyError::Write)?;
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self, packet: &[u8]) -> Result<()> {
+        for (index, value) in parse_rref(packet)? {
+            if let Some(slot) = usize::try_from(index)
+                .ok()
+                .and_then(|index| self.values.get_mut(index))
+            {
+                *slot = Some(value);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Source for RrefSource {
+    fn recv_frame(&mut self) -> Result<PflyIpcData> {
+        let mut buffer = [0u8; 2048];
+
+        loop {
+            let (len, from) = self
+                .socket
+                .recv_from(&mut buffer)
+                .map_err(PflyError::Source)?;
+
+            if from != self.xplane {
+                continue;
+            }
+
+            self.update(&buffer[..len])?;
+
+            if let Some(frame) = self.frame() {
+                return Ok(frame);
+            }
+        }
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.resubscribe()
+    }
+}
+
+impl Drop for RrefSource {
+    fn drop(&mut self) {
+        let _ = self.subscribe(0);
+    }
+}
+
+/// Builds an `RREF` request asking for `path` as `index`, a `frequency` of 0 unsubscribes.
+fn rref_request(frequency: i32, index: i32, path: &str) -> Vec<u8> {
+    let mut request = Vec::with_capacity(5 + 8 + RREF_PATH_LEN);
+
+    request.extend_from_slice(b"RREF\0");
+    request.extend_from_slice(&frequency.to_le_bytes());
+    request.extend_from_slice(&index.to_le_bytes());
+
+    let path = &path.as_bytes()[..path.len().min(RREF_PATH_LEN - 1)];
+    request.extend_from_slice(path);
+    request.resize(5 + 8 + RREF_PATH_LEN, 0);
+
+    request
+}
+
+/// Splits an `RREF` response into its `(index, value)` pairs.
+fn parse_rref(packet: &[u8]) -> Result<Vec<(i32, f32)>> {
+    if packet.len() < 5 || &packet[..4] != b"RREF" {
+        return Err(PflyError::MalformedPacket("not an RREF response"));
+    }
+
+    let records = packet[5..].chunks_exact(8);
+    if !records.remainder().is_empty() {
+        return Err(PflyError::MalformedPacket("truncated RREF record"));
+    }
+
+    Ok(records
+        .map(|record| {
+            let index = i32::from_le_bytes(record[..4].try_into().unwrap());
+            let value = f32::from_le_bytes(record[4..].try_into().unwrap());
+            (index, value)
+        })
+        .collect())
+}
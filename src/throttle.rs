@@ -0,0 +1,167 @@
+use crate::PflyIpcData;
+use std::time::{Duration, Instant};
+
+/// How much a frame has to differ from the last one sent to be worth sending.
+///
+/// Used with [`PflyConnectionBuilder::send_on_change`]. A field counts as changed once it moved
+/// at least its threshold away from the last frame that went out, so slow drifts still get sent
+/// eventually. Flags, the bridge type, the aircraft type and `landingVerticalSpeed` count on any
+/// change, and a frame always goes out once `heartbeat` has passed so projectFly doesn't think
+/// the bridge died while parked.
+///
+/// # Example
+///
+/// ```
+/// use pfly_rust::ChangeThresholds;
+/// use std::time::Duration;
+///
+/// let thresholds = ChangeThresholds {
+///     altitude: 50,
+///     heartbeat: Duration::from_secs(5),
+///     ..ChangeThresholds::default()
+/// };
+/// ```
+///
+/// [`PflyConnectionBuilder::send_on_change`]: crate::PflyConnectionBuilder::send_on_change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangeThresholds {
+    /// Feet, for both the altitude and the height above ground.
+    pub altitude: i32,
+    /// Knots, for both the ground speed and the indicated airspeed.
+    pub speed: i32,
+    /// Degrees, for both headings.
+    pub heading: i32,
+    /// Degrees of latitude or longitude.
+    pub position: f64,
+    /// Feet per minute.
+    pub vertical_speed: i32,
+    /// G multiplied by 1000, like `gForce`.
+    pub g_force: i32,
+    pub fuel: i32,
+    /// Degrees, for both the pitch and the roll.
+    pub attitude: i32,
+    pub fps: i32,
+    /// Longest time between two frames, changed or not.
+    pub heartbeat: Duration,
+}
+
+impl Default for ChangeThresholds {
+    fn default() -> ChangeThresholds {
+        ChangeThresholds {
+            altitude: 10,
+            speed: 1,
+            heading: 1,
+            // About 10 meters.
+            position: 0.0001,
+            vertical_speed: 50,
+            g_force: 50,
+            fuel: 10,
+            attitude: 1,
+            fps: 5,
+            heartbeat: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ChangeThresholds {
+    /// Whether `frame` differs enough from `last` to be sent.
+    pub fn changed(&self, last: &PflyIpcData, frame: &PflyIpcData) -> bool {
+        let moved = |a: i32, b: i32, threshold: i32| a.abs_diff(b) >= threshold.max(1) as u32;
+        let turned = |a: i32, b: i32| {
+            let diff = (i64::from(a) - i64::from(b)).rem_euclid(360);
+            diff.min(360 - diff) >= i64::from(self.heading.max(1))
+        };
+
+        moved(last.altitude, frame.altitude, self.altitude)
+            || moved(last.agl, frame.agl, self.altitude)
+            || moved(last.groundspeed, frame.groundspeed, self.speed)
+            || moved(last.ias, frame.ias, self.speed)
+            || turned(last.headingTrue, frame.headingTrue)
+            || turned(last.headingMagnetic, frame.headingMagnetic)
+            || (last.latitude - frame.latitude).abs() >= self.position
+            || (last.longitude - frame.longitude).abs() >= self.position
+            || moved(last.verticalSpeed, frame.verticalSpeed, self.vertical_speed)
+            || moved(last.gForce, frame.gForce, self.g_force)
+            || moved(last.fuel, frame.fuel, self.fuel)
+            || moved(last.pitch, frame.pitch, self.attitude)
+            || moved(last.roll, frame.roll, self.attitude)
+            || moved(last.fps, frame.fps, self.fps)
+            || last.landingVerticalSpeed != frame.landingVerticalSpeed
+            || last.transponder != frame.transponder
+            || last.bridgeType != frame.bridgeType
+            || last.isOnGround != frame.isOnGround
+            || last.isSlew != frame.isSlew
+            || last.isPaused != frame.isPaused
+            || last.aircraftType != frame.aircraftType
+    }
+}
+
+/// Decides which frames of a fast stream actually go out.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Throttle {
+    min_interval: Option<Duration>,
+    thresholds: Option<ChangeThresholds>,
+    last_sent: Option<(PflyIpcData, Instant)>,
+    /// The latest frame that was held back, for [`Throttle::take_pending`].
+    pending: Option<PflyIpcData>,
+}
+
+impl Throttle {
+    /// `None` when neither limit is set, so connections without one skip all of this.
+    pub(crate) fn new(
+        max_rate: Option<f64>,
+        thresholds: Option<ChangeThresholds>,
+    ) -> Option<Throttle> {
+        // Rates that don't make an interval, like 0, NaN or 1e-30, mean no limit.
+        let min_interval = max_rate.and_then(|rate| Duration::try_from_secs_f64(rate.recip()).ok());
+
+        if min_interval.is_none() && thresholds.is_none() {
+            return None;
+        }
+
+        Some(Throttle {
+            min_interval,
+            thresholds,
+            ..Throttle::default()
+        })
+    }
+
+    /// Whether `frame` should be sent at `now`, otherwise it is kept as the pending frame.
+    pub(crate) fn offer(&mut self, frame: &PflyIpcData, now: Instant) -> bool {
+        let send = match &self.last_sent {
+            None => true,
+            Some((last, sent_at)) => {
+                let elapsed = now.saturating_duration_since(*sent_at);
+                let due = match self.min_interval {
+                    Some(interval) => elapsed >= interval,
+                    None => true,
+                };
+                let changed = match &self.thresholds {
+                    Some(thresholds) => {
+                        elapsed >= thresholds.heartbeat || thresholds.changed(last, frame)
+                    }
+                    None => true,
+                };
+
+                due && changed
+            }
+        };
+
+        if !send {
+            self.pending = Some(frame.clone());
+        }
+
+        send
+    }
+
+    /// Remembers that `frame` went out at `now`.
+    pub(crate) fn sent(&mut self, frame: &PflyIpcData, now: Instant) {
+        self.last_sent = Some((frame.clone(), now));
+        self.pending = None;
+    }
+
+    /// The latest frame that was held back since the last send.
+    pub(crate) fn take_pending(&mut self) -> Option<PflyIpcData> {
+        self.pending.take()
+    }
+}
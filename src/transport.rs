@@ -0,0 +1,275 @@
+//! How frames get to projectFly.
+//!
+//! projectFly listens on a Unix socket, which is what [`UnixTransport`] talks to and what
+//! [`PflyConnection`] uses unless told otherwise. When projectFly runs in a VM or on another
+//! PC, [`TcpTransport`] sends the very same bytes over TCP instead, pick it with
+//! [`PflyConnectionBuilder::tcp`]. projectFly itself doesn't listen on TCP, so on its end
+//! something has to pass the stream on to its socket, e.g.
+//! `socat TCP-LISTEN:4500,fork UNIX-CONNECT:/tmp/pf.sock`.
+//!
+//! Anything else that can carry a byte stream can implement [`Transport`] and be handed to
+//! [`PflyConnectionBuilder::with_transport`].
+//!
+//! ```no_run
+//! let mut connection = pfly_rust::PflyConnection::builder()
+//!     .tcp("192.168.1.20:4500")
+//!     .connect()?;
+//! # Ok::<(), pfly_rust::PflyError>(())
+//! ```
+//!
+//! [`PflyConnection`]: crate::PflyConnection
+//! [`PflyConnectionBuilder::tcp`]: crate::PflyConnectionBuilder::tcp
+//! [`PflyConnectionBuilder::with_transport`]: crate::PflyConnectionBuilder::with_transport
+
+use crate::{PflyError, Result};
+use socket2::{Domain, SockAddr, Socket, Type};
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+use std::time::Duration;
+
+/// A byte stream to projectFly that frames are written to.
+///
+/// Transports only move bytes, the encoding is always the one from [`wire`].
+///
+/// [`wire`]: crate::wire
+pub trait Transport: fmt::Debug + Send {
+    /// Writes the whole payload, failing with [`PflyError::Write`] if it can't.
+    ///
+    /// Errors that mean the other end went away should keep their `io::ErrorKind`, so
+    /// [`PflyError::is_disconnect`] and with it [`ReconnectingConnection`] work as usual.
+    ///
+    /// [`ReconnectingConnection`]: crate::ReconnectingConnection
+    fn send(&mut self, payload: &[u8]) -> Result<()>;
+
+    /// Hangs up. Dropping the transport must do the same, this only reports errors.
+    fn close(&mut self) -> Result<()>;
+
+    /// The socket underneath, for transports that have one.
+    fn socket(&self) -> Option<&Socket> {
+        None
+    }
+
+    /// Gives back the socket underneath, for transports that have one.
+    fn into_socket(self: Box<Self>) -> Option<Socket> {
+        None
+    }
+}
+
+/// projectFly's own Unix socket, the default.
+#[derive(Debug)]
+pub struct UnixTransport {
+    socket: Socket,
+}
+
+impl UnixTransport {
+    /// Connects to the socket at `path`, giving up after `timeout` if there is one.
+    pub fn connect<P: AsRef<Path>>(path: P, timeout: Option<Duration>) -> Result<UnixTransport> {
+        let socket =
+            Socket::new(Domain::unix(), Type::stream(), None).map_err(PflyError::Socket)?;
+        let addr = SockAddr::unix(path).map_err(PflyError::Address)?;
+
+        match timeout {
+            Some(timeout) => socket.connect_timeout(&addr, timeout),
+            None => socket.connect(&addr),
+        }
+        .map_err(PflyError::from_connect)?;
+
+        Ok(UnixTransport { socket })
+    }
+
+    /// Wraps a socket that is already connected to projectFly, e.g. one returned by [`init`].
+    ///
+    /// [`init`]: crate::init
+    pub fn new(socket: Socket) -> UnixTransport {
+        UnixTransport { socket }
+    }
+
+    /// Puts the socket in or out of non-blocking mode, see [`PflyConnectionBuilder::nonblocking`].
+    ///
+    /// [`PflyConnectionBuilder::nonblocking`]: crate::PflyConnectionBuilder::nonblocking
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        self.socket
+            .set_nonblocking(nonblocking)
+            .map_err(PflyError::Socket)
+    }
+
+    /// Gives back the socket, keeping it open.
+    pub fn into_socket(self) -> Socket {
+        self.socket
+    }
+}
+
+impl From<Socket> for UnixTransport {
+    fn from(socket: Socket) -> UnixTransport {
+        UnixTransport::new(socket)
+    }
+}
+
+impl Transport for UnixTransport {
+    fn send(&mut self, payload: &[u8]) -> Result<()> {
+        (&self.socket).write_all(payload).map_err(PflyError::Write)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        shutdown(self.socket.shutdown(Shutdown::Both))
+    }
+
+    fn socket(&self) -> Option<&Socket> {
+        Some(&self.socket)
+    }
+
+    fn into_socket(self: Box<Self>) -> Option<Socket> {
+        Some(self.socket)
+    }
+}
+
+/// A TCP stream to something that passes frames on to projectFly on another host.
+///
+/// Nagle's algorithm is turned off, frames are small and should go out right away.
+#[derive(Debug)]
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    /// Connects to `addr`, trying each address it resolves to in turn.
+    ///
+    /// With a `timeout`, each of those attempts gives up after it. A name that doesn't
+    /// resolve fails with [`PflyError::Address`].
+    pub fn connect<A: ToSocketAddrs>(addr: A, timeout: Option<Duration>) -> Result<TcpTransport> {
+        let mut last_err = None;
+
+        for addr in addr.to_socket_addrs().map_err(PflyError::Address)? {
+            let stream = match timeout {
+                Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+                None => TcpStream::connect(addr),
+            };
+
+            match stream {
+                Ok(stream) => {
+                    stream.set_nodelay(true).map_err(PflyError::Socket)?;
+                    return Ok(TcpTransport { stream });
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(match last_err {
+            Some(err) => PflyError::from_connect(err),
+            None => PflyError::Address(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "address did not resolve to anything",
+            )),
+        })
+    }
+
+    /// Wraps a stream that is already connected.
+    pub fn new(stream: TcpStream) -> TcpTransport {
+        TcpTransport { stream }
+    }
+
+    /// Puts the stream in or out of non-blocking mode, see [`PflyConnectionBuilder::nonblocking`].
+    ///
+    /// [`PflyConnectionBuilder::nonblocking`]: crate::PflyConnectionBuilder::nonblocking
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        self.stream
+            .set_nonblocking(nonblocking)
+            .map_err(PflyError::Socket)
+    }
+
+    /// Returns the underlying stream.
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+}
+
+impl From<TcpStream> for TcpTransport {
+    fn from(stream: TcpStream) -> TcpTransport {
+        TcpTransport::new(stream)
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, payload: &[u8]) -> Result<()> {
+        self.stream.write_all(payload).map_err(PflyError::Write)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        shutdown(self.stream.shutdown(Shutdown::Both))
+    }
+}
+
+/// One UDP datagram per frame, for `pfly-relay`.
+///
+/// Nothing tells the sender whether frames arrive, a relay that is down looks just like one
+/// that is up. Fine for telemetry where the next frame is never far away, not much else.
+#[derive(Debug)]
+pub struct UdpTransport {
+    socket: UdpSocket,
+}
+
+impl UdpTransport {
+    /// Sends to `addr` from a random local port.
+    ///
+    /// A name that doesn't resolve fails with [`PflyError::Address`].
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<UdpTransport> {
+        let addr = addr
+            .to_socket_addrs()
+            .map_err(PflyError::Address)?
+            .next()
+            .ok_or_else(|| {
+                PflyError::Address(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "address did not resolve to anything",
+                ))
+            })?;
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+
+        let socket = UdpSocket::bind(local).map_err(PflyError::Socket)?;
+        socket.connect(addr).map_err(PflyError::from_connect)?;
+
+        Ok(UdpTransport { socket })
+    }
+
+    /// Returns the underlying socket.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, payload: &[u8]) -> Result<()> {
+        let sent = self.socket.send(payload).map_err(PflyError::Write)?;
+        check_datagram(sent, payload)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The other end hanging up first is fine when we are closing anyway.
+fn shutdown(result: io::Result<()>) -> Result<()> {
+    match result {
+        Err(err) if err.kind() != io::ErrorKind::NotConnected => Err(PflyError::Write(err)),
+        _ => Ok(()),
+    }
+}
+
+/// Fails when only `sent` bytes of `payload` made it into the datagram.
+pub(crate) fn check_datagram(sent: usize, payload: &[u8]) -> Result<()> {
+    if sent < payload.len() {
+        return Err(PflyError::Write(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "frame did not fit into one datagram",
+        )));
+    }
+
+    Ok(())
+}
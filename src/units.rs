@@ -0,0 +1,121 @@
+//! Physical quantities with their unit attached.
+//!
+//! [`PflyIpcData`] stores everything as plain numbers in the units projectFly expects:
+//! feet, knots, feet per minute and degrees. These types let you hand over whatever the
+//! simulator gives you and leave the conversion to [`PflyIpcDataBuilder`].
+//!
+//! ```
+//! use pfly_rust::units::{Length, Speed};
+//!
+//! assert_eq!(Length::Meters(1000.0).feet().round(), 3281.0);
+//! assert_eq!(Speed::MetersPerSecond(100.0).knots().round(), 194.0);
+//! ```
+//!
+//! [`PflyIpcData`]: crate::PflyIpcData
+//! [`PflyIpcDataBuilder`]: crate::PflyIpcDataBuilder
+
+/// Meters in a foot.
+pub const METERS_PER_FOOT: f64 = 0.3048;
+
+/// Feet in a meter.
+pub const FEET_PER_METER: f64 = 1.0 / METERS_PER_FOOT;
+
+/// Meters per second in a knot.
+pub const METERS_PER_SECOND_PER_KNOT: f64 = 1852.0 / 3600.0;
+
+/// Knots in a meter per second.
+pub const KNOTS_PER_METER_PER_SECOND: f64 = 1.0 / METERS_PER_SECOND_PER_KNOT;
+
+/// Kilograms in a pound, for fuel reported in pounds.
+pub const KILOGRAMS_PER_POUND: f64 = 0.453_592_37;
+
+/// A length, e.g. an altitude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Feet(f64),
+    Meters(f64),
+}
+
+impl Length {
+    pub fn feet(self) -> f64 {
+        match self {
+            Length::Feet(feet) => feet,
+            Length::Meters(meters) => meters / METERS_PER_FOOT,
+        }
+    }
+
+    pub fn meters(self) -> f64 {
+        match self {
+            Length::Feet(feet) => feet * METERS_PER_FOOT,
+            Length::Meters(meters) => meters,
+        }
+    }
+}
+
+/// A horizontal speed, e.g. indicated airspeed or ground speed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    Knots(f64),
+    MetersPerSecond(f64),
+    KilometersPerHour(f64),
+}
+
+impl Speed {
+    pub fn knots(self) -> f64 {
+        match self {
+            Speed::Knots(knots) => knots,
+            Speed::MetersPerSecond(mps) => mps / METERS_PER_SECOND_PER_KNOT,
+            Speed::KilometersPerHour(kmh) => kmh / 1.852,
+        }
+    }
+
+    pub fn meters_per_second(self) -> f64 {
+        self.knots() * METERS_PER_SECOND_PER_KNOT
+    }
+}
+
+/// A climb or descent rate, negative when descending.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerticalSpeed {
+    FeetPerMinute(f64),
+    MetersPerSecond(f64),
+}
+
+impl VerticalSpeed {
+    pub fn feet_per_minute(self) -> f64 {
+        match self {
+            VerticalSpeed::FeetPerMinute(fpm) => fpm,
+            VerticalSpeed::MetersPerSecond(mps) => mps / METERS_PER_FOOT * 60.0,
+        }
+    }
+
+    pub fn meters_per_second(self) -> f64 {
+        match self {
+            VerticalSpeed::FeetPerMinute(fpm) => fpm * METERS_PER_FOOT / 60.0,
+            VerticalSpeed::MetersPerSecond(mps) => mps,
+        }
+    }
+}
+
+/// An angle, e.g. a heading, a coordinate or the pitch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Angle {
+    Degrees(f64),
+    Radians(f64),
+}
+
+impl Angle {
+    pub fn degrees(self) -> f64 {
+        match self {
+            Angle::Degrees(degrees) => degrees,
+            Angle::Radians(radians) => radians.to_degrees(),
+        }
+    }
+
+    pub fn radians(self) -> f64 {
+        match self {
+            Angle::Degrees(degrees) => degrees.to_radians(),
+            Angle::Radians(radians) => radians,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::PflyIpcData;
+use std::fmt;
+
+/// One reason a [`PflyIpcData`] frame can't describe a real aircraft.
+///
+/// Returned by [`PflyIpcData::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// A floating point field is NaN or infinite.
+    NotFinite { field: &'static str },
+    /// A field lies outside of what is physically possible.
+    OutOfRange {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// A heading outside of `0..360`, it should have been wrapped around.
+    Heading { field: &'static str, value: i32 },
+    /// A transponder code that isn't four octal digits, like `9999`.
+    NonOctalSquawk(i32),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::NotFinite { field } => write!(f, "{} is not a finite number", field),
+            Violation::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } => write!(f, "{} is {}, expected {} to {}", field, value, min, max),
+            Violation::Heading { field, value } => {
+                write!(f, "{} is {}, expected 0 to 359", field, value)
+            }
+            Violation::NonOctalSquawk(squawk) => {
+                write!(f, "transponder {:04} is not a valid squawk code", squawk)
+            }
+        }
+    }
+}
+
+impl PflyIpcData {
+    /// Checks the frame for values no aircraft could report.
+    ///
+    /// All problems are collected rather than stopping at the first one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pfly_rust::units::Angle;
+    /// use pfly_rust::{PflyIpcData, Violation};
+    ///
+    /// let mut frame = PflyIpcData::builder()
+    ///     .position(Angle::Degrees(43.68), Angle::Degrees(-79.63))
+    ///     .transponder(7000)
+    ///     .build();
+    /// assert!(frame.validate().is_ok());
+    ///
+    /// frame.transponder = 9999;
+    /// frame.latitude = f64::NAN;
+    /// assert_eq!(
+    ///     frame.validate().unwrap_err(),
+    ///     vec![
+    ///         Violation::NotFinite { field: "latitude" },
+    ///         Violation::NonOctalSquawk(9999),
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+
+        check_coordinate(&mut violations, "latitude", self.latitude, 90.0);
+        check_coordinate(&mut violations, "longitude", self.longitude, 180.0);
+
+        check_range(&mut violations, "altitude", self.altitude, -2_000, 100_000);
+        check_range(&mut violations, "groundspeed", self.groundspeed, 0, 2_000);
+        check_range(&mut violations, "ias", self.ias, 0, 2_000);
+        check_range(&mut violations, "fuel", self.fuel, 0, i32::MAX);
+        check_range(&mut violations, "pitch", self.pitch, -90, 90);
+        check_range(&mut violations, "roll", self.roll, -180, 180);
+        check_range(&mut violations, "fps", self.fps, 0, i32::MAX);
+
+        check_heading(&mut violations, "headingTrue", self.headingTrue);
+        check_heading(&mut violations, "headingMagnetic", self.headingMagnetic);
+
+        if !is_octal_squawk(self.transponder) {
+            violations.push(Violation::NonOctalSquawk(self.transponder));
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+fn check_coordinate(violations: &mut Vec<Violation>, field: &'static str, value: f64, max: f64) {
+    if !value.is_finite() {
+        violations.push(Violation::NotFinite { field });
+    } else if value < -max || value > max {
+        violations.push(Violation::OutOfRange {
+            field,
+            value,
+            min: -max,
+            max,
+        });
+    }
+}
+
+fn check_range(
+    violations: &mut Vec<Violation>,
+    field: &'static str,
+    value: i32,
+    min: i32,
+    max: i32,
+) {
+    if value < min || value > max {
+        violations.push(Violation::OutOfRange {
+            field,
+            value: f64::from(value),
+            min: f64::from(min),
+            max: f64::from(max),
+        });
+    }
+}
+
+fn check_heading(violations: &mut Vec<Violation>, field: &'static str, value: i32) {
+    if !(0..360).contains(&value) {
+        violations.push(Violation::Heading { field, value });
+    }
+}
+
+fn is_octal_squawk(squawk: i32) -> bool {
+    (0..=7777).contains(&squawk)
+        && squawk
+            .to_string()
+            .chars()
+            .all(|digit| ('0'..='7').contains(&digit))
+}
@@ -0,0 +1,211 @@
+//! The exact bytes projectFly reads off its socket for each [`PflyIpcData`].
+//!
+//! projectFly's `flightsim.service.ts` parses frames by hand at fixed offsets, so this layout
+//! is the contract, not whatever a serialization library happens to produce. Every number is
+//! little-endian, with no padding in between:
+//!
+//! | Offset | Size | Field                  | Type                    |
+//! |-------:|-----:|------------------------|-------------------------|
+//! |      0 |    4 | `altitude`             | `i32`                   |
+//! |      4 |    4 | `agl`                  | `i32`                   |
+//! |      8 |    4 | `groundspeed`          | `i32`                   |
+//! |     12 |    4 | `ias`                  | `i32`                   |
+//! |     16 |    4 | `headingTrue`          | `i32`                   |
+//! |     20 |    4 | `headingMagnetic`      | `i32`                   |
+//! |     24 |    8 | `latitude`             | `f64`                   |
+//! |     32 |    8 | `longitude`            | `f64`                   |
+//! |     40 |    4 | `verticalSpeed`        | `i32`                   |
+//! |     44 |    4 | `landingVerticalSpeed` | `i32`                   |
+//! |     48 |    4 | `gForce`               | `i32`                   |
+//! |     52 |    4 | `fuel`                 | `i32`                   |
+//! |     56 |    4 | `transponder`          | `i32`                   |
+//! |     60 |    1 | `bridgeType`           | `u8`, see [`BridgeType`] |
+//! |     61 |    1 | `isOnGround`           | `u8`, 0 or 1            |
+//! |     62 |    1 | `isSlew`               | `u8`, 0 or 1            |
+//! |     63 |    1 | `isPaused`             | `u8`, 0 or 1            |
+//! |     64 |    4 | `pitch`                | `i32`                   |
+//! |     68 |    4 | `roll`                 | `i32`                   |
+//! |     72 |    4 | `time`                 | `i32`                   |
+//! |     76 |    4 | `fps`                  | `i32`                   |
+//! |     80 |    8 | length of `aircraftType` in bytes | `u64`        |
+//! |     88 |    n | `aircraftType`         | UTF-8, not terminated   |
+//!
+//! This happens to be what bincode 1's default settings made of the struct, which is what
+//! [`send_message`] used to send, and the tests make sure it stays that way.
+//!
+//! ```
+//! use pfly_rust::{wire, PflyIpcData};
+//!
+//! let frame = PflyIpcData { altitude: 569, aircraftType: "B77W".into(), ..PflyIpcData::default() };
+//! let bytes = wire::encode(&frame);
+//!
+//! assert_eq!(bytes.len(), wire::FIXED_LEN + 8 + 4);
+//! assert_eq!(bytes[..4], 569i32.to_le_bytes());
+//! assert_eq!(wire::decode(&bytes)?, (frame, bytes.len()));
+//! # Ok::<(), pfly_rust::PflyError>(())
+//! ```
+//!
+//! [`PflyIpcData`]: crate::PflyIpcData
+//! [`BridgeType`]: crate::BridgeType
+//! [`send_message`]: crate::send_message
+
+use crate::{BridgeType, PflyError, PflyIpcData, Result};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::io::{self, Read};
+
+/// Size of everything before the `aircraftType` length.
+pub const FIXED_LEN: usize = 80;
+
+/// Longest `aircraftType` [`read_frame`] accepts, so a corrupt length can't make it allocate
+/// gigabytes.
+pub const MAX_AIRCRAFT_TYPE_LEN: usize = 4096;
+
+/// How many bytes [`encode`] makes of `frame`.
+pub fn encoded_len(frame: &PflyIpcData) -> usize {
+    FIXED_LEN + 8 + frame.aircraftType.len()
+}
+
+/// Turns a frame into the bytes projectFly expects.
+pub fn encode(frame: &PflyIpcData) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(encoded_len(frame));
+    encode_into(frame, &mut bytes);
+    bytes
+}
+
+/// Like [`encode`], appending to `bytes` so a buffer can be reused between frames.
+pub fn encode_into(frame: &PflyIpcData, bytes: &mut Vec<u8>) {
+    bytes.reserve(encoded_len(frame));
+
+    for value in &[
+        frame.altitude,
+        frame.agl,
+        frame.groundspeed,
+        frame.ias,
+        frame.headingTrue,
+        frame.headingMagnetic,
+    ] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&frame.latitude.to_le_bytes());
+    bytes.extend_from_slice(&frame.longitude.to_le_bytes());
+    for value in &[
+        frame.verticalSpeed,
+        frame.landingVerticalSpeed,
+        frame.gForce,
+        frame.fuel,
+        frame.transponder,
+    ] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.push(u8::from(frame.bridgeType));
+    bytes.push(u8::from(frame.isOnGround));
+    bytes.push(u8::from(frame.isSlew));
+    bytes.push(u8::from(frame.isPaused));
+    for value in &[frame.pitch, frame.roll, frame.time, frame.fps] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&(frame.aircraftType.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(frame.aircraftType.as_bytes());
+}
+
+/// How long the frame at the start of `bytes` is, `None` until its length is in there.
+///
+/// Handy for splitting a byte stream that is forwarded as it comes in, see [`decode`].
+pub fn frame_len(bytes: &[u8]) -> Option<u64> {
+    let len = bytes.get(FIXED_LEN..FIXED_LEN + 8)?;
+    Some(u64::from_le_bytes(array(len)).saturating_add((FIXED_LEN + 8) as u64))
+}
+
+/// Reads one frame from the start of `bytes`.
+///
+/// Returns the frame and how many bytes it took up, anything after that is left alone.
+/// Fails with [`PflyError::MalformedFrame`] if `bytes` ends early or holds values the layout
+/// doesn't allow, like a bool that isn't 0 or 1.
+pub fn decode(bytes: &[u8]) -> Result<(PflyIpcData, usize)> {
+    if bytes.len() < FIXED_LEN + 8 {
+        return Err(PflyError::MalformedFrame("frame is truncated"));
+    }
+
+    let (fixed, len) = bytes[..FIXED_LEN + 8].split_at(FIXED_LEN);
+    let len = usize::try_from(u64::from_le_bytes(array(len)))
+        .map_err(|_| PflyError::MalformedFrame("aircraft type is too long"))?;
+    let end = (FIXED_LEN + 8)
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or(PflyError::MalformedFrame("frame is truncated"))?;
+
+    let frame = decode_parts(fixed, &bytes[FIXED_LEN + 8..end])?;
+    Ok((frame, end))
+}
+
+/// Reads the next frame from a stream, e.g. a connection accepted in place of projectFly.
+///
+/// A stream that ends cleanly between two frames gives an error of kind `UnexpectedEof`,
+/// a malformed frame one of kind `InvalidData` wrapping the [`PflyError::MalformedFrame`].
+/// An `aircraftType` longer than [`MAX_AIRCRAFT_TYPE_LEN`] counts as malformed.
+pub fn read_frame<R: Read>(mut reader: R) -> io::Result<PflyIpcData> {
+    let mut fixed = [0; FIXED_LEN + 8];
+    reader.read_exact(&mut fixed)?;
+
+    let len = u64::from_le_bytes(array(&fixed[FIXED_LEN..]));
+    if len > MAX_AIRCRAFT_TYPE_LEN as u64 {
+        return Err(invalid_data(PflyError::MalformedFrame(
+            "aircraft type is too long",
+        )));
+    }
+
+    let mut aircraft_type = vec![0; len as usize];
+    reader.read_exact(&mut aircraft_type)?;
+
+    decode_parts(&fixed[..FIXED_LEN], &aircraft_type).map_err(invalid_data)
+}
+
+fn invalid_data(err: PflyError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn decode_parts(fixed: &[u8], aircraft_type: &[u8]) -> Result<PflyIpcData> {
+    let int = |offset: usize| i32::from_le_bytes(array(&fixed[offset..offset + 4]));
+    let float = |offset: usize| f64::from_le_bytes(array(&fixed[offset..offset + 8]));
+    let flag = |offset: usize| match fixed[offset] {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(PflyError::MalformedFrame("flag is neither 0 nor 1")),
+    };
+
+    let aircraft_type = String::from_utf8(aircraft_type.to_vec())
+        .map_err(|_| PflyError::MalformedFrame("aircraft type is not UTF-8"))?;
+
+    Ok(PflyIpcData {
+        altitude: int(0),
+        agl: int(4),
+        groundspeed: int(8),
+        ias: int(12),
+        headingTrue: int(16),
+        headingMagnetic: int(20),
+        latitude: float(24),
+        longitude: float(32),
+        verticalSpeed: int(40),
+        landingVerticalSpeed: int(44),
+        gForce: int(48),
+        fuel: int(52),
+        transponder: int(56),
+        bridgeType: BridgeType::from(fixed[60]),
+        isOnGround: flag(61)?,
+        isSlew: flag(62)?,
+        isPaused: flag(63)?,
+        pitch: int(64),
+        roll: int(68),
+        time: int(72),
+        fps: int(76),
+        aircraftType: Cow::Owned(aircraft_type),
+    })
+}
+
+/// Copies a slice of known length into an array for `from_le_bytes`.
+pub(crate) fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(bytes);
+    array
+}
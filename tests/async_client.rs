@@ -0,0 +1,108 @@
+#![cfg(feature = "tokio")]
+
+use pfly_rust::async_client::AsyncPflyConnection;
+use pfly_rust::mock::MockServer;
+use pfly_rust::relay::{self, Verifier};
+use pfly_rust::units::Length;
+use pfly_rust::{PflyConnection, PflyError, PflyIpcData};
+use std::time::Duration;
+
+#[tokio::test]
+async fn sends_frames_like_send_message() {
+    let server = MockServer::start().unwrap();
+    let mut connection = AsyncPflyConnection::connect_with(&server.connection_builder())
+        .await
+        .unwrap();
+
+    for feet in 0..100 {
+        let frame = PflyIpcData::builder()
+            .altitude(Length::Feet(f64::from(feet)))
+            .aircraft_type("A20N")
+            .build();
+        connection.send(&frame).await.unwrap();
+    }
+
+    let received: Vec<_> = server.iter().take(100).collect();
+    assert_eq!(received.last().unwrap().altitude, 99);
+    assert_eq!(received[0].aircraftType, "A20N");
+}
+
+#[tokio::test]
+async fn sends_frames_over_tcp() {
+    let server = MockServer::start_tcp().unwrap();
+    let mut connection = AsyncPflyConnection::connect_with(&server.connection_builder())
+        .await
+        .unwrap();
+
+    let frame = PflyIpcData::builder()
+        .altitude(Length::Feet(3500.0))
+        .build();
+    connection.send(&frame).await.unwrap();
+
+    assert_eq!(server.recv_timeout(Duration::from_secs(2)), Some(frame));
+    assert!(connection.into_stream().is_none());
+}
+
+#[tokio::test]
+async fn signs_frames_over_udp() {
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let builder = PflyConnection::builder()
+        .udp(socket.local_addr().unwrap().to_string())
+        .relay_key("correct horse battery staple");
+    let mut connection = AsyncPflyConnection::connect_with(&builder).await.unwrap();
+
+    let frame = PflyIpcData::builder().aircraft_type("B38M").build();
+    connection.send(&frame).await.unwrap();
+
+    let mut buffer = vec![0; relay::MAX_ENVELOPE_LEN];
+    let (len, peer) = socket.recv_from(&mut buffer).unwrap();
+    let opened = Verifier::new("correct horse battery staple")
+        .verify(peer.ip(), &buffer[..len])
+        .unwrap();
+    assert_eq!(opened.frame, frame);
+}
+
+#[tokio::test]
+async fn relay_key_needs_a_network_address() {
+    let server = MockServer::start().unwrap();
+    let builder = server
+        .connection_builder()
+        .relay_key("correct horse battery staple");
+
+    let err = AsyncPflyConnection::connect_with(&builder)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PflyError::Misconfigured(_)));
+}
+
+#[tokio::test]
+async fn missing_socket_is_not_found() {
+    let server = MockServer::start().unwrap();
+    let builder = server.connection_builder();
+    drop(server);
+
+    let err = AsyncPflyConnection::connect_with(&builder)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PflyError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn strict_mode_carries_over_from_builder() {
+    let server = MockServer::start().unwrap();
+    let mut connection =
+        AsyncPflyConnection::connect_with(&server.connection_builder().strict(true))
+            .await
+            .unwrap();
+
+    let mut frame = PflyIpcData::builder().build();
+    frame.latitude = 500.0;
+    assert!(matches!(
+        connection.send(&frame).await,
+        Err(PflyError::Invalid(_))
+    ));
+    assert!(server.recv_timeout(Duration::from_millis(100)).is_none());
+}
@@ -0,0 +1,24 @@
+use pfly_rust::BridgeType;
+
+#[test]
+fn every_index_round_trips() {
+    for value in 0..=u8::MAX {
+        assert_eq!(u8::from(BridgeType::from(value)), value);
+    }
+
+    assert_eq!(BridgeType::from(0), BridgeType::SimConnect);
+    assert_eq!(BridgeType::from(2), BridgeType::InfiniteFlight);
+    assert_eq!(BridgeType::from(3), BridgeType::XPlane);
+    assert_eq!(BridgeType::from(4), BridgeType::Other(4));
+    assert_eq!(BridgeType::default(), BridgeType::XPlane);
+}
+
+#[test]
+fn serializes_as_the_index() {
+    assert_eq!(serde_json::to_string(&BridgeType::Fsuipc).unwrap(), "1");
+    assert_eq!(serde_json::to_string(&BridgeType::Other(42)).unwrap(), "42");
+
+    let other: BridgeType = serde_json::from_str("42").unwrap();
+    assert_eq!(other, BridgeType::Other(42));
+    assert!(serde_json::from_str::<BridgeType>("256").is_err());
+}
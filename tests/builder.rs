@@ -0,0 +1,95 @@
+use pfly_rust::units::{Angle, Length, Speed, VerticalSpeed};
+use pfly_rust::PflyIpcData;
+
+fn heading(degrees: f64) -> i32 {
+    PflyIpcData::builder()
+        .heading_true(Angle::Degrees(degrees))
+        .build()
+        .headingTrue
+}
+
+fn altitude(feet: f64) -> i32 {
+    PflyIpcData::builder()
+        .altitude(Length::Feet(feet))
+        .build()
+        .altitude
+}
+
+#[test]
+fn headings_wrap_into_0_to_360() {
+    assert_eq!(heading(0.0), 0);
+    assert_eq!(heading(359.4), 359);
+    assert_eq!(heading(360.0), 0);
+    assert_eq!(heading(359.6), 0);
+    assert_eq!(heading(725.0), 5);
+    assert_eq!(heading(-90.0), 270);
+    assert_eq!(heading(-0.4), 0);
+    assert_eq!(heading(-720.0), 0);
+
+    let magnetic = PflyIpcData::builder()
+        .heading_magnetic(Angle::Radians(-std::f64::consts::FRAC_PI_2))
+        .build();
+    assert_eq!(magnetic.headingMagnetic, 270);
+}
+
+#[test]
+fn rounds_to_the_nearest_whole_number() {
+    assert_eq!(altitude(568.5), 569);
+    assert_eq!(altitude(568.49), 568);
+    assert_eq!(altitude(-0.5), -1);
+    assert_eq!(altitude(-2.4), -2);
+}
+
+#[test]
+fn non_finite_values_saturate_or_become_zero() {
+    assert_eq!(altitude(f64::NAN), 0);
+    assert_eq!(altitude(f64::INFINITY), i32::MAX);
+    assert_eq!(altitude(f64::NEG_INFINITY), i32::MIN);
+    assert_eq!(altitude(1e20), i32::MAX);
+
+    assert_eq!(heading(f64::NAN), 0);
+    assert_eq!(heading(f64::INFINITY), 0);
+
+    let frame = PflyIpcData::builder().g_force(f64::NAN).build();
+    assert_eq!(frame.gForce, 0);
+}
+
+#[test]
+fn converts_into_projectfly_units() {
+    let frame = PflyIpcData::builder()
+        .altitude(Length::Meters(1000.0))
+        .agl(Length::Feet(12.0))
+        .groundspeed(Speed::KilometersPerHour(1.852 * 140.0))
+        .ias(Speed::Knots(151.0))
+        .vertical_speed(VerticalSpeed::MetersPerSecond(-5.08))
+        .landing_vertical_speed(VerticalSpeed::FeetPerMinute(-182.4))
+        .pitch(Angle::Radians(0.1))
+        .roll(Angle::Degrees(-12.6))
+        .fps(59.7)
+        .g_force(1.2345)
+        .build();
+
+    assert_eq!(frame.altitude, 3281);
+    assert_eq!(frame.agl, 12);
+    assert_eq!(frame.groundspeed, 140);
+    assert_eq!(frame.ias, 151);
+    assert_eq!(frame.verticalSpeed, -1000);
+    assert_eq!(frame.landingVerticalSpeed, -182);
+    assert_eq!(frame.pitch, 6);
+    assert_eq!(frame.roll, -13);
+    assert_eq!(frame.fps, 60);
+    assert_eq!(frame.gForce, 1235);
+}
+
+#[test]
+fn unset_fields_are_zero_except_g() {
+    let frame = PflyIpcData::builder().build();
+
+    assert_eq!(
+        frame,
+        PflyIpcData {
+            gForce: 1000,
+            ..PflyIpcData::default()
+        }
+    );
+}
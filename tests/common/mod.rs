@@ -0,0 +1,29 @@
+use pfly_rust::{BridgeType, PflyIpcData};
+
+/// A valid frame of a B77W parked at Toronto, told apart by its altitude.
+pub fn frame(altitude: i32) -> PflyIpcData {
+    PflyIpcData {
+        altitude,
+        agl: 0,
+        groundspeed: 0,
+        ias: 0,
+        headingTrue: 0,
+        headingMagnetic: 0,
+        latitude: 43.6772222,
+        longitude: -79.6305556,
+        verticalSpeed: 0,
+        landingVerticalSpeed: 0,
+        gForce: 1000,
+        fuel: 20000,
+        transponder: 1425,
+        bridgeType: BridgeType::XPlane,
+        isOnGround: true,
+        isSlew: false,
+        isPaused: false,
+        pitch: 0,
+        roll: 0,
+        time: 0,
+        fps: 120,
+        aircraftType: "B77W".into(),
+    }
+}
@@ -0,0 +1,73 @@
+use pfly_rust::{PflyConnection, PflyError, Violation};
+use std::error::Error;
+use std::io;
+use std::os::unix::net::UnixListener;
+
+#[test]
+fn connect_failures_say_which_step_failed() {
+    let dir = std::env::temp_dir().join(format!("pfly-error-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let missing = PflyConnection::builder()
+        .path(dir.join("missing.sock"))
+        .connect();
+    assert!(matches!(missing, Err(PflyError::NotFound(_))));
+
+    // The file stays behind after the listener is gone, nobody accepts on it any more.
+    let stale = dir.join("stale.sock");
+    drop(UnixListener::bind(&stale).unwrap());
+    let refused = PflyConnection::builder().path(&stale).connect();
+    assert!(matches!(refused, Err(PflyError::ConnectionRefused(_))));
+
+    let too_long = PflyConnection::builder()
+        .path(dir.join("x".repeat(200)))
+        .connect();
+    assert!(too_long.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn messages() {
+    let err = PflyError::NotFound(io::Error::from(io::ErrorKind::NotFound));
+    assert_eq!(
+        err.to_string(),
+        "projectFly socket not found, is projectFly running?"
+    );
+
+    let err = PflyError::Write(io::Error::new(io::ErrorKind::BrokenPipe, "gone"));
+    assert_eq!(
+        err.to_string(),
+        "could not write to projectFly socket: gone"
+    );
+
+    let err = PflyError::Invalid(vec![
+        Violation::NotFinite { field: "latitude" },
+        Violation::NonOctalSquawk(9999),
+    ]);
+    assert_eq!(
+        err.to_string(),
+        "refusing to send invalid frame: latitude is not a finite number, \
+         transponder 9999 is not a valid squawk code"
+    );
+
+    assert_eq!(
+        PflyError::MalformedFrame("frame is truncated").to_string(),
+        "malformed projectFly frame: frame is truncated"
+    );
+    assert_eq!(
+        PflyError::Disconnected.to_string(),
+        "not connected to projectFly"
+    );
+}
+
+#[test]
+fn io_errors_are_the_source() {
+    let err = PflyError::Connect(io::Error::new(io::ErrorKind::PermissionDenied, "denied"));
+    let source = err.source().unwrap();
+    assert_eq!(source.to_string(), "denied");
+
+    assert!(PflyError::Disconnected.source().is_none());
+    assert!(PflyError::Unauthenticated.source().is_none());
+    assert!(PflyError::Invalid(Vec::new()).source().is_none());
+}
@@ -0,0 +1,55 @@
+use pfly_rust::sources::flightgear::{parse_line, FlightGearSource, PROTOCOL_XML};
+use pfly_rust::sources::Source;
+use pfly_rust::BridgeType;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+#[test]
+fn protocol_file_matches_parser_column_count() {
+    // 17 numeric columns plus the aircraft name.
+    assert_eq!(PROTOCOL_XML.matches("<chunk>").count(), 18);
+    assert!(PROTOCOL_XML.contains("<node>/sim/aircraft</node>"));
+}
+
+#[test]
+fn receives_lines_over_udp() {
+    let mut source = FlightGearSource::bind("127.0.0.1:0").unwrap();
+    source
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+
+    let flightgear = UdpSocket::bind("127.0.0.1:0").unwrap();
+    flightgear
+        .send_to(
+            b"3500.4,2931.0,152.6,140.2,-10.0,355.3,37.6188056,-122.3754167,-11.25,1.120,2400.0,4512,0,0,-2.6,14.8,55,737-800\n",
+            source.local_addr().unwrap(),
+        )
+        .unwrap();
+
+    let frame = source.recv_frame().unwrap();
+    assert_eq!(frame.altitude, 3500);
+    assert_eq!(frame.agl, 2931);
+    assert_eq!(frame.groundspeed, 153);
+    assert_eq!(frame.ias, 140);
+    assert_eq!(frame.headingTrue, 350);
+    assert_eq!(frame.headingMagnetic, 355);
+    assert_eq!(frame.latitude, 37.6188056);
+    assert_eq!(frame.longitude, -122.3754167);
+    assert_eq!(frame.verticalSpeed, -675);
+    assert_eq!(frame.gForce, 1120);
+    assert_eq!(frame.fuel, 1089);
+    assert_eq!(frame.transponder, 4512);
+    assert!(!frame.isOnGround);
+    assert!(!frame.isPaused);
+    assert_eq!(frame.pitch, -3);
+    assert_eq!(frame.roll, 15);
+    assert_eq!(frame.fps, 55);
+    assert_eq!(frame.bridgeType, BridgeType::XPlane);
+    assert_eq!(frame.aircraftType, "737-800");
+}
+
+#[test]
+fn rejects_short_lines() {
+    assert!(parse_line("1.0,2.0,3.0").is_err());
+    assert!(parse_line("a,b,c,d,e,f,g,h,i,j,k,l,m,n,o,p,q,r").is_err());
+}
@@ -0,0 +1,105 @@
+use pfly_rust::landing::LandingDetector;
+use pfly_rust::mock::MockServer;
+use pfly_rust::PflyIpcData;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+fn frame(vertical_speed: i32, g_force: i32, on_ground: bool) -> PflyIpcData {
+    PflyIpcData {
+        verticalSpeed: vertical_speed,
+        gForce: g_force,
+        isOnGround: on_ground,
+        ..PflyIpcData::default()
+    }
+}
+
+#[test]
+fn takes_the_rate_from_before_contact() {
+    let mut detector = LandingDetector::new();
+    let start = Instant::now();
+    let frames = [
+        frame(-700, 1000, false),
+        frame(-310, 1100, false),
+        frame(-260, 1150, false),
+        frame(-240, 1050, false),
+        // The simulator already zeroed most of the rate by the time it says we're down.
+        frame(-15, 1420, true),
+        frame(0, 1610, true),
+        frame(0, 1010, true),
+    ];
+
+    // 20 frames a second after a gap, the -700 is too long ago to count.
+    let times = [0, 400, 450, 500, 550, 600, 650];
+
+    let mut sent = Vec::new();
+    let mut landings = Vec::new();
+    for (mut frame, time) in frames.iter().cloned().zip(times.iter()) {
+        let at = start + Duration::from_millis(*time);
+        landings.extend(detector.apply_at(&mut frame, at));
+        sent.push(frame);
+    }
+
+    assert_eq!(landings.len(), 1);
+    assert_eq!(landings[0].vertical_speed, -310);
+    assert_eq!(landings[0].bounces, 0);
+
+    let landing = detector.landing().unwrap();
+    assert_eq!(landing.g_force, 1610);
+
+    assert!(sent[..4]
+        .iter()
+        .all(|frame| frame.landingVerticalSpeed == 0));
+    assert!(sent[4..]
+        .iter()
+        .all(|frame| frame.landingVerticalSpeed == -310));
+    assert_eq!(sent[4].gForce, 1420);
+    assert_eq!(sent[5].gForce, 1610);
+    assert_eq!(sent[6].gForce, 1610);
+}
+
+#[test]
+fn bounces_keep_the_first_rate() {
+    let mut detector = LandingDetector::new();
+    let start = Instant::now();
+    let mut at = start;
+    let mut apply = |frame: PflyIpcData| {
+        at += Duration::from_millis(100);
+        let mut frame = frame;
+        detector.apply_at(&mut frame, at)
+    };
+
+    apply(frame(-400, 1000, false));
+    let first = apply(frame(-50, 1500, true)).unwrap();
+    apply(frame(200, 900, false));
+    apply(frame(-100, 950, false));
+    let second = apply(frame(-20, 1900, true)).unwrap();
+
+    assert_eq!(first.vertical_speed, -400);
+    assert_eq!(second.vertical_speed, -400);
+    assert_eq!(second.bounces, 1);
+    assert_eq!(second.g_force, 1900);
+    assert_eq!(second.at, first.at);
+}
+
+#[test]
+fn connections_fill_in_outgoing_frames() {
+    let server = MockServer::start().unwrap();
+    let mut connection = server
+        .connection_builder()
+        .detect_landings(true)
+        .connect()
+        .unwrap();
+
+    connection.send(&frame(-180, 1000, false)).unwrap();
+    connection.send(&frame(0, 1300, true)).unwrap();
+
+    assert_eq!(
+        server.recv_timeout(TIMEOUT).unwrap().landingVerticalSpeed,
+        0
+    );
+    let touchdown = server.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(touchdown.landingVerticalSpeed, -180);
+    assert_eq!(touchdown.gForce, 1300);
+    assert_eq!(connection.landing().unwrap().vertical_speed, -180);
+}
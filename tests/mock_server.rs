@@ -0,0 +1,146 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::{ConnectionState, PflyConnection, ReconnectingConnection};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+use common::frame;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+#[test]
+fn connection_sends_many_frames_on_one_socket() {
+    let server = MockServer::start().unwrap();
+    let mut connection = server.connection_builder().connect().unwrap();
+
+    for altitude in 0..1000 {
+        connection.send(&frame(altitude)).unwrap();
+    }
+
+    let altitudes: Vec<i32> = server
+        .iter()
+        .take(1000)
+        .map(|frame| frame.altitude)
+        .collect();
+    assert_eq!(altitudes, (0..1000).collect::<Vec<_>>());
+    assert_eq!(server.connections(), 1);
+}
+
+#[test]
+fn socket_outlives_the_connection() {
+    let server = MockServer::start().unwrap();
+    let mut connection = server.connection_builder().connect().unwrap();
+    connection.send(&frame(1)).unwrap();
+
+    let socket = connection.into_socket().unwrap();
+    pfly_rust::send_message(&socket, &frame(2)).unwrap();
+    pfly_rust::send_message(&socket, &frame(3)).unwrap();
+
+    let mut connection = PflyConnection::from(socket);
+    connection.send(&frame(4)).unwrap();
+    connection.close().unwrap();
+
+    let altitudes: Vec<i32> = server.iter().take(4).map(|frame| frame.altitude).collect();
+    assert_eq!(altitudes, [1, 2, 3, 4]);
+    assert_eq!(server.connections(), 1);
+}
+
+#[test]
+fn builder_picks_the_target() {
+    let builder = PflyConnection::builder().path("/run/user/1000/pf.sock");
+    assert_eq!(builder.socket_path(), Path::new("/run/user/1000/pf.sock"));
+    assert_eq!(builder.tcp_address(), None);
+    assert_eq!(builder.udp_address(), None);
+
+    // The last one set wins.
+    let builder = builder.tcp("192.0.2.1:4000");
+    assert_eq!(builder.tcp_address(), Some("192.0.2.1:4000"));
+    let builder = builder.udp("192.0.2.1:4001");
+    assert_eq!(builder.tcp_address(), None);
+    assert_eq!(builder.udp_address(), Some("192.0.2.1:4001"));
+    let builder = builder.path("/tmp/other.sock");
+    assert_eq!(builder.udp_address(), None);
+    assert_eq!(builder.socket_path(), Path::new("/tmp/other.sock"));
+}
+
+#[test]
+fn connect_to_missing_socket_is_not_found() {
+    let server = MockServer::start().unwrap();
+    let path = server.path().to_path_buf();
+    drop(server);
+
+    let err = PflyConnection::builder().path(path).connect().unwrap_err();
+    assert!(matches!(err, pfly_rust::PflyError::NotFound(_)));
+    assert!(err.is_disconnect());
+}
+
+#[test]
+fn reconnects_after_projectfly_restarts() {
+    let server = MockServer::start().unwrap();
+    let path = server.path().to_path_buf();
+    let (states, state_changes) = mpsc::channel();
+
+    let mut connection = ReconnectingConnection::new(server.connection_builder())
+        .buffer_latest(true)
+        .on_state_change(move |state| states.send(state).unwrap());
+
+    connection.send(&frame(1)).unwrap();
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 1);
+
+    drop(server);
+
+    // The first writes after the peer is gone may still succeed, keep going until one fails.
+    let mut altitude = 2;
+    while connection.send(&frame(altitude)).is_ok() {
+        altitude += 1;
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_ne!(connection.state(), ConnectionState::Connected);
+    assert!(connection.has_pending());
+
+    let server = MockServer::bind(&path).unwrap();
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while connection.state() != ConnectionState::Connected {
+        assert!(std::time::Instant::now() < deadline);
+        let _ = connection.poll();
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, altitude);
+    assert!(!connection.has_pending());
+
+    let states: Vec<_> = state_changes.try_iter().collect();
+    assert_eq!(states.first(), Some(&ConnectionState::Connected));
+    assert!(states.contains(&ConnectionState::Disconnected));
+    assert_eq!(states.last(), Some(&ConnectionState::Connected));
+}
+
+#[test]
+fn frames_round_trip_with_runtime_aircraft_type() {
+    let server = MockServer::start().unwrap();
+    let mut connection = server.connection_builder().connect().unwrap();
+
+    let mut sent = frame(35000);
+    sent.aircraftType = String::from("A20N").into();
+    connection.send(&sent).unwrap();
+
+    assert_eq!(server.recv_timeout(TIMEOUT), Some(sent));
+}
+
+#[test]
+fn strict_connection_refuses_invalid_frames() {
+    let server = MockServer::start().unwrap();
+    let mut connection = server.connection_builder().strict(true).connect().unwrap();
+
+    let mut invalid = frame(1);
+    invalid.headingTrue = 720;
+    let err = connection.send(&invalid).unwrap_err();
+    assert!(matches!(err, pfly_rust::PflyError::Invalid(_)));
+    assert!(!err.is_disconnect());
+
+    connection.send(&frame(2)).unwrap();
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 2);
+}
@@ -0,0 +1,118 @@
+use pfly_rust::phase::{FlightPhase, PhaseDetector};
+use pfly_rust::simulate::Flight;
+use pfly_rust::units::{Angle, Length, Speed};
+use pfly_rust::PflyIpcData;
+use std::time::{Duration, Instant};
+
+fn airborne(agl: i32, vertical_speed: i32) -> PflyIpcData {
+    PflyIpcData {
+        altitude: agl,
+        agl,
+        groundspeed: 140,
+        ias: 140,
+        verticalSpeed: vertical_speed,
+        ..PflyIpcData::default()
+    }
+}
+
+fn on_ground(groundspeed: i32) -> PflyIpcData {
+    PflyIpcData {
+        groundspeed,
+        ias: groundspeed,
+        isOnGround: true,
+        ..PflyIpcData::default()
+    }
+}
+
+#[test]
+fn follows_a_whole_flight() {
+    let flight = Flight::new(
+        (Angle::Degrees(43.6772), Angle::Degrees(-79.6306)),
+        (Angle::Degrees(45.4706), Angle::Degrees(-73.7408)),
+    )
+    .cruise_altitude(Length::Feet(24000.0))
+    .cruise_speed(Speed::Knots(420.0));
+
+    let mut detector = PhaseDetector::new();
+    let start = Instant::now();
+
+    let changes: Vec<_> = flight
+        .frames()
+        .enumerate()
+        .filter_map(|(i, frame)| detector.update_at(&frame, start + Duration::from_secs(i as u64)))
+        .collect();
+
+    let phases: Vec<_> = changes.iter().map(|change| change.to).collect();
+    assert_eq!(
+        phases,
+        [
+            FlightPhase::Boarding,
+            FlightPhase::Taxi,
+            FlightPhase::Takeoff,
+            FlightPhase::Climb,
+            FlightPhase::Cruise,
+            FlightPhase::Descent,
+            FlightPhase::Approach,
+            FlightPhase::Landed,
+        ]
+    );
+
+    assert!(changes.windows(2).all(|pair| pair[0].at < pair[1].at));
+    assert!(changes
+        .windows(2)
+        .all(|pair| pair[1].from == Some(pair[0].to)));
+}
+
+#[test]
+fn bounces_are_one_landing() {
+    let mut detector = PhaseDetector::new();
+    let start = Instant::now();
+    let mut at = start;
+    let mut feed = |frame: PflyIpcData| {
+        at += Duration::from_secs(1);
+        detector.update_at(&frame, at)
+    };
+
+    feed(airborne(1500, -700));
+    for _ in 0..5 {
+        feed(airborne(300, -700));
+    }
+
+    let mut changes = Vec::new();
+    for frame in vec![
+        on_ground(130),
+        airborne(5, 300),
+        airborne(8, 100),
+        on_ground(125),
+        airborne(2, -100),
+        on_ground(120),
+        on_ground(110),
+        on_ground(100),
+        on_ground(90),
+    ] {
+        changes.extend(feed(frame));
+    }
+
+    let phases: Vec<_> = changes.iter().map(|change| change.to).collect();
+    assert_eq!(phases, [FlightPhase::Landed]);
+    // Reported from the last touchdown, the earlier ones never lasted long enough.
+    assert_eq!(changes[0].at, start + Duration::from_secs(12));
+}
+
+#[test]
+fn ignores_paused_frames() {
+    let mut detector = PhaseDetector::new().hold(Duration::from_secs(0));
+
+    detector.update(&airborne(30000, 0));
+    assert_eq!(detector.phase(), Some(FlightPhase::Cruise));
+
+    let paused = PflyIpcData {
+        isPaused: true,
+        ..airborne(30000, -3000)
+    };
+    assert!(detector.update(&paused).is_none());
+
+    let change = detector.update(&airborne(29000, -3000)).unwrap();
+    assert_eq!(change.from, Some(FlightPhase::Cruise));
+    assert_eq!(change.to, FlightPhase::Descent);
+}
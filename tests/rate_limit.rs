@@ -0,0 +1,118 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::{ChangeThresholds, PflyIpcData};
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+use common::frame;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+const QUIET: Duration = Duration::from_millis(100);
+
+#[test]
+fn max_rate_coalesces_to_the_latest_frame() {
+    let server = MockServer::start().unwrap();
+    let mut connection = server.connection_builder().max_rate(5.0).connect().unwrap();
+
+    for altitude in 0..100 {
+        connection.send(&frame(altitude)).unwrap();
+    }
+
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 0);
+    assert!(server.recv_timeout(QUIET).is_none());
+
+    // The source went quiet, the last frame still has to get there.
+    connection.flush().unwrap();
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 99);
+    connection.flush().unwrap();
+    assert!(server.recv_timeout(QUIET).is_none());
+
+    thread::sleep(Duration::from_millis(200));
+    connection.send(&frame(100)).unwrap();
+    connection.send(&frame(101)).unwrap();
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 100);
+    assert!(server.recv_timeout(QUIET).is_none());
+}
+
+#[test]
+fn send_on_change_skips_small_changes() {
+    let server = MockServer::start().unwrap();
+    let mut connection = server
+        .connection_builder()
+        .send_on_change(ChangeThresholds {
+            altitude: 10,
+            heartbeat: Duration::from_millis(300),
+            ..ChangeThresholds::default()
+        })
+        .connect()
+        .unwrap();
+
+    for altitude in &[1000, 1000, 1004, 1009, 1011, 1015] {
+        connection.send(&frame(*altitude)).unwrap();
+    }
+
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 1000);
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 1011);
+    assert!(server.recv_timeout(QUIET).is_none());
+
+    // Nothing changed, but projectFly still hears from us.
+    thread::sleep(Duration::from_millis(300));
+    connection.send(&frame(1015)).unwrap();
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 1015);
+
+    let airborne = PflyIpcData {
+        isOnGround: false,
+        ..frame(1015)
+    };
+    connection.send(&airborne).unwrap();
+    assert!(!server.recv_timeout(TIMEOUT).unwrap().isOnGround);
+}
+
+#[test]
+fn headings_wrap_around() {
+    let thresholds = ChangeThresholds {
+        heading: 3,
+        ..ChangeThresholds::default()
+    };
+    let north = |heading| PflyIpcData {
+        headingTrue: heading,
+        headingMagnetic: heading,
+        ..PflyIpcData::default()
+    };
+
+    assert!(!thresholds.changed(&north(359), &north(1)));
+    assert!(thresholds.changed(&north(358), &north(1)));
+    assert!(thresholds.changed(&north(i32::MIN), &north(i32::MAX)));
+}
+
+#[test]
+fn extreme_values_still_compare() {
+    let thresholds = ChangeThresholds::default();
+    let at = |altitude| PflyIpcData {
+        altitude,
+        ..PflyIpcData::default()
+    };
+
+    assert!(thresholds.changed(&at(i32::MIN), &at(1)));
+    assert!(thresholds.changed(&at(i32::MAX), &at(i32::MIN)));
+    assert!(!thresholds.changed(&at(i32::MIN), &at(i32::MIN)));
+}
+
+#[test]
+fn unusable_rates_mean_no_limit() {
+    let server = MockServer::start().unwrap();
+
+    for rate in [1e-30, 0.0, -5.0, f64::NAN, f64::INFINITY] {
+        let mut connection = server
+            .connection_builder()
+            .max_rate(rate)
+            .connect()
+            .unwrap();
+        connection.send(&frame(1)).unwrap();
+        connection.send(&frame(2)).unwrap();
+
+        assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 1);
+        assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 2);
+    }
+}
@@ -0,0 +1,71 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::{Backoff, PflyError, ReconnectingConnection, Violation};
+use std::io;
+use std::time::Duration;
+
+mod common;
+
+use common::frame;
+
+#[test]
+fn backoff_stays_between_zero_and_max() {
+    let backoff = Backoff::default();
+    assert_eq!(backoff.delay(1), Duration::from_millis(250));
+    assert_eq!(backoff.delay(3), Duration::from_secs(1));
+    assert_eq!(backoff.delay(1000), backoff.max);
+
+    let negative = Backoff {
+        multiplier: -2.0,
+        ..Backoff::default()
+    };
+    assert_eq!(negative.delay(2), Duration::ZERO);
+    assert_eq!(negative.delay(3), Duration::from_secs(1));
+
+    let nan = Backoff {
+        multiplier: f64::NAN,
+        ..Backoff::default()
+    };
+    assert_eq!(nan.delay(2), nan.max);
+
+    // Reconnecting with it doesn't panic either.
+    let server = MockServer::start().unwrap();
+    let builder = server.connection_builder();
+    drop(server);
+    let mut connection = ReconnectingConnection::new(builder).backoff(negative);
+    for _ in 0..3 {
+        assert!(connection.send(&frame(0)).is_err());
+    }
+}
+
+#[test]
+fn backoff_grows_up_to_max() {
+    let backoff = Backoff {
+        initial: Duration::from_millis(100),
+        max: Duration::from_millis(500),
+        multiplier: 3.0,
+        jitter: 0.0,
+    };
+
+    assert_eq!(backoff.delay(0), Duration::from_millis(100));
+    assert_eq!(backoff.delay(1), Duration::from_millis(100));
+    assert_eq!(backoff.delay(2), Duration::from_millis(300));
+    assert_eq!(backoff.delay(3), Duration::from_millis(500));
+    assert_eq!(backoff.delay(u32::MAX), Duration::from_millis(500));
+}
+
+#[test]
+fn only_lost_connections_count_as_disconnects() {
+    let kind = io::Error::from;
+
+    assert!(PflyError::NotFound(kind(io::ErrorKind::NotFound)).is_disconnect());
+    assert!(PflyError::ConnectionRefused(kind(io::ErrorKind::ConnectionRefused)).is_disconnect());
+    assert!(PflyError::Connect(kind(io::ErrorKind::TimedOut)).is_disconnect());
+    assert!(PflyError::Connect(kind(io::ErrorKind::ConnectionReset)).is_disconnect());
+    assert!(PflyError::Write(kind(io::ErrorKind::BrokenPipe)).is_disconnect());
+    assert!(PflyError::Disconnected.is_disconnect());
+
+    assert!(!PflyError::Connect(kind(io::ErrorKind::PermissionDenied)).is_disconnect());
+    assert!(!PflyError::Write(kind(io::ErrorKind::WouldBlock)).is_disconnect());
+    assert!(!PflyError::Socket(kind(io::ErrorKind::Other)).is_disconnect());
+    assert!(!PflyError::Invalid(vec![Violation::NonOctalSquawk(9999)]).is_disconnect());
+}
@@ -0,0 +1,136 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::record::{self, Recorder};
+use pfly_rust::{PflyIpcData, ReconnectingConnection};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+use common::frame;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pfly-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn recordings(dir: &PathBuf) -> Vec<PathBuf> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn records_what_was_sent() {
+    let dir = temp_dir("record-sent");
+    let server = MockServer::start().unwrap();
+    let recorder = Recorder::create(&dir).unwrap();
+    let path = recorder.path().to_path_buf();
+
+    let mut connection =
+        ReconnectingConnection::new(server.connection_builder().recorder(recorder));
+    for altitude in 0..3 {
+        connection.send(&frame(altitude)).unwrap();
+    }
+    for _ in 0..3 {
+        server.recv_timeout(TIMEOUT).unwrap();
+    }
+    drop(connection);
+
+    let records: Vec<_> = record::read(&path)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(records.len(), 3);
+    for (altitude, record) in records.iter().enumerate() {
+        assert_eq!(record.frame, frame(altitude as i32));
+        assert!(record.timestamp > 0);
+    }
+    assert!(records[0].timestamp <= records[2].timestamp);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rotates_and_keeps_the_newest_files() {
+    let dir = temp_dir("record-rotate");
+    let mut recorder = Recorder::create(&dir)
+        .unwrap()
+        .max_file_size(1)
+        .max_files(2);
+
+    for altitude in 0..5 {
+        recorder
+            .record_at(&frame(altitude), UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap();
+    }
+    recorder.flush().unwrap();
+
+    let files = recordings(&dir);
+    assert_eq!(files.len(), 2);
+
+    // One frame per file, only the last two are left.
+    let last = record::read(recorder.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(last.frame.altitude, 4);
+    assert_eq!(last.timestamp, 1000);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn malformed_lines_are_errors() {
+    let dir = temp_dir("record-malformed");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("broken.jsonl");
+    std::fs::write(&path, "\n{\"timestamp\": 5}\n").unwrap();
+
+    let mut records = record::read(&path).unwrap();
+    assert!(matches!(
+        records.next(),
+        Some(Err(pfly_rust::PflyError::Record(_)))
+    ));
+    assert!(records.next().is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn non_finite_coordinates_read_back() {
+    let dir = temp_dir("record-non-finite");
+    let mut recorder = Recorder::create(&dir).unwrap();
+    let path = recorder.path().to_path_buf();
+
+    let garbage = PflyIpcData {
+        latitude: f64::NAN,
+        longitude: f64::NEG_INFINITY,
+        ..frame(569)
+    };
+    recorder.record(&garbage).unwrap();
+    recorder.record(&frame(570)).unwrap();
+    recorder.flush().unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.contains("\"latitude\":\"NaN\""));
+    assert!(text.contains("\"longitude\":\"-inf\""));
+
+    let records: Vec<_> = record::read(&path)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(records[0].frame.latitude.is_nan());
+    assert_eq!(records[0].frame.longitude, f64::NEG_INFINITY);
+    assert_eq!(records[0].frame.altitude, 569);
+    assert_eq!(records[1].frame, frame(570));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,170 @@
+use pfly_rust::relay::{self, Rejection, SignedTransport, Verifier};
+use pfly_rust::transport::TcpTransport;
+use pfly_rust::{wire, PflyConnection, PflyError};
+use std::net::{IpAddr, TcpListener, UdpSocket};
+use std::time::{Duration, SystemTime};
+
+mod common;
+
+use common::frame;
+
+const KEY: &str = "correct horse battery staple";
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+fn source() -> IpAddr {
+    "192.168.1.20".parse().unwrap()
+}
+
+#[test]
+fn opens_sealed_frames() {
+    let sent = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    let envelope = relay::seal(KEY, &wire::encode(&frame(569)), 7, sent);
+    assert_eq!(relay::envelope_len(&envelope), Some(envelope.len()));
+    assert_eq!(envelope[..4], relay::MAGIC);
+
+    let opened = relay::open(KEY, &envelope).unwrap();
+    assert_eq!(opened.timestamp, 1_700_000_000_123);
+    assert_eq!(opened.sequence, 7);
+    assert_eq!(opened.frame, frame(569));
+}
+
+#[test]
+fn rejects_tampered_frames() {
+    let envelope = relay::seal(KEY, &wire::encode(&frame(569)), 1, SystemTime::now());
+
+    let result = relay::open("Tr0ub4dor&3", &envelope);
+    assert!(matches!(result, Err(PflyError::Unauthenticated)));
+
+    let mut tampered = envelope.clone();
+    tampered[relay::HEADER_LEN] ^= 1;
+    assert!(matches!(
+        relay::open(KEY, &tampered),
+        Err(PflyError::Unauthenticated)
+    ));
+
+    let truncated = &envelope[..envelope.len() - 1];
+    assert!(matches!(
+        relay::open(KEY, truncated),
+        Err(PflyError::MalformedFrame(_))
+    ));
+    assert!(matches!(
+        relay::open(KEY, &wire::encode(&frame(569))),
+        Err(PflyError::MalformedFrame(_))
+    ));
+}
+
+#[test]
+fn rejects_old_and_replayed_frames() {
+    let now = SystemTime::now();
+    let seal = |sequence, sent| relay::seal(KEY, &wire::encode(&frame(0)), sequence, sent);
+    let mut verifier = Verifier::new(KEY).max_skew(Duration::from_secs(5));
+
+    let old = seal(1, now - Duration::from_secs(10));
+    assert_eq!(
+        verifier.verify_at(source(), &old, now),
+        Err(Rejection::Expired)
+    );
+    let early = seal(1, now + Duration::from_secs(10));
+    assert_eq!(
+        verifier.verify_at(source(), &early, now),
+        Err(Rejection::Expired)
+    );
+
+    let first = seal(1, now);
+    assert!(verifier.verify_at(source(), &first, now).is_ok());
+    assert_eq!(
+        verifier.verify_at(source(), &first, now),
+        Err(Rejection::Replayed)
+    );
+    // Another address has its own count.
+    assert!(verifier
+        .verify_at("10.0.0.5".parse().unwrap(), &first, now)
+        .is_ok());
+
+    let second = seal(2, now);
+    assert!(verifier.verify_at(source(), &second, now).is_ok());
+    assert_eq!(
+        verifier.verify_at(source(), &first, now),
+        Err(Rejection::Replayed)
+    );
+
+    // A bridge that reconnected starts counting at 1 again, but later.
+    let reconnected = seal(1, now + Duration::from_millis(1));
+    assert!(verifier.verify_at(source(), &reconnected, now).is_ok());
+
+    let forged = relay::seal("Tr0ub4dor&3", &wire::encode(&frame(0)), 3, now);
+    assert_eq!(
+        verifier.verify_at(source(), &forged, now),
+        Err(Rejection::Unauthenticated)
+    );
+}
+
+#[test]
+fn signs_frames_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let mut connection = PflyConnection::builder()
+        .tcp(address.to_string())
+        .relay_key(KEY)
+        .connect()
+        .unwrap();
+    let (stream, peer) = listener.accept().unwrap();
+
+    for altitude in 0..10 {
+        connection.send(&frame(altitude)).unwrap();
+    }
+    connection.close().unwrap();
+
+    let mut verifier = Verifier::new(KEY);
+    for altitude in 0..10 {
+        let envelope = relay::read_envelope(&stream).unwrap();
+        let opened = verifier.verify(peer.ip(), &envelope).unwrap();
+        assert_eq!(opened.sequence, altitude as u64 + 1);
+        assert_eq!(opened.frame, frame(altitude));
+    }
+    let end = relay::read_envelope(&stream).unwrap_err();
+    assert_eq!(end.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn signs_frames_over_udp() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+    let mut connection = PflyConnection::builder()
+        .udp(socket.local_addr().unwrap().to_string())
+        .relay_key(KEY)
+        .connect()
+        .unwrap();
+    connection.send(&frame(569)).unwrap();
+
+    let mut buffer = vec![0; relay::MAX_ENVELOPE_LEN];
+    let (len, peer) = socket.recv_from(&mut buffer).unwrap();
+    let opened = Verifier::new(KEY)
+        .verify(peer.ip(), &buffer[..len])
+        .unwrap();
+    assert_eq!(opened.frame, frame(569));
+}
+
+#[test]
+fn signed_transport_wraps_any_transport() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let inner = TcpTransport::connect(listener.local_addr().unwrap(), Some(TIMEOUT)).unwrap();
+    let mut connection = PflyConnection::with_transport(SignedTransport::new(inner, KEY));
+    let (stream, _) = listener.accept().unwrap();
+
+    connection.send(&frame(1)).unwrap();
+
+    let envelope = relay::read_envelope(&stream).unwrap();
+    assert_eq!(relay::open(KEY, &envelope).unwrap().frame, frame(1));
+}
+
+#[test]
+fn relay_key_needs_a_network_address() {
+    let server = pfly_rust::mock::MockServer::start().unwrap();
+    let result = server.connection_builder().relay_key(KEY).connect();
+    assert!(matches!(result, Err(PflyError::Misconfigured(_))));
+    assert_eq!(server.connections(), 0);
+}
@@ -0,0 +1,158 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::record::{Record, Recorder};
+use pfly_rust::replay::Replay;
+use pfly_rust::PflyIpcData;
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+fn records(timestamps: &[u64]) -> Vec<Record> {
+    timestamps
+        .iter()
+        .enumerate()
+        .map(|(i, &timestamp)| Record {
+            timestamp,
+            frame: PflyIpcData {
+                altitude: i as i32,
+                ..PflyIpcData::default()
+            },
+        })
+        .collect()
+}
+
+#[test]
+fn replays_a_recording_into_projectfly() {
+    let dir = std::env::temp_dir().join(format!("pfly-replay-{}", std::process::id()));
+    let mut recorder = Recorder::create(&dir).unwrap();
+    for record in records(&[0, 100, 200]) {
+        recorder
+            .record_at(
+                &record.frame,
+                UNIX_EPOCH + Duration::from_millis(record.timestamp),
+            )
+            .unwrap();
+    }
+    recorder.flush().unwrap();
+
+    let server = MockServer::start().unwrap();
+    let mut connection = server.connection_builder().connect().unwrap();
+
+    let replay = Replay::open(recorder.path()).unwrap();
+    assert_eq!(replay.len(), 3);
+    assert_eq!(replay.duration(), Duration::from_millis(200));
+
+    let start = Instant::now();
+    assert_eq!(replay.run(|frame| connection.send(frame)).unwrap(), 3);
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    for altitude in 0..3 {
+        assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, altitude);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn speed_and_seek() {
+    let replay = Replay::new(records(&[0, 1000, 2000, 3000]))
+        .speed(10.0)
+        .seek(Duration::from_millis(1500));
+
+    let mut altitudes = Vec::new();
+    let start = Instant::now();
+    replay
+        .run(|frame| {
+            altitudes.push(frame.altitude);
+            Ok(())
+        })
+        .unwrap();
+
+    // 2000 and 3000 are left, 1.5s of recording at 10x is 150ms.
+    assert_eq!(altitudes, [2, 3]);
+    assert!(start.elapsed() >= Duration::from_millis(150));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn seek_past_a_clock_step_back() {
+    // The last frame was recorded after the clock went back, before the seek point.
+    let replay = Replay::new(records(&[1000, 5000, 3000]))
+        .speed(10.0)
+        .seek(Duration::from_secs(3));
+
+    let mut altitudes = Vec::new();
+    replay
+        .run(|frame| {
+            altitudes.push(frame.altitude);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(altitudes, [1, 2]);
+}
+
+#[test]
+fn pause_holds_frames_back() {
+    let replay = Replay::new(records(&[0, 100]));
+    let control = replay.control();
+    control.pause();
+
+    let resume = {
+        let control = control.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            control.resume();
+        })
+    };
+
+    let start = Instant::now();
+    assert_eq!(replay.run(|_| Ok(())).unwrap(), 2);
+    assert!(start.elapsed() >= Duration::from_millis(400));
+
+    resume.join().unwrap();
+}
+
+#[test]
+fn loops_until_stopped() {
+    let replay = Replay::new(records(&[0, 10])).looping(true);
+    let control = replay.control();
+
+    let mut altitudes = Vec::new();
+    let sent = replay
+        .run(|frame| {
+            altitudes.push(frame.altitude);
+            if altitudes.len() == 5 {
+                control.stop();
+            }
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(sent, 5);
+    assert_eq!(altitudes, [0, 1, 0, 1, 0]);
+}
+
+#[test]
+fn tiny_speeds_wait_until_stopped() {
+    let replay = Replay::new(records(&[0, 1000])).speed(1e-20);
+    let control = replay.control();
+
+    let stop = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        control.stop();
+    });
+
+    let mut altitudes = Vec::new();
+    let sent = replay
+        .run(|frame| {
+            altitudes.push(frame.altitude);
+            Ok(())
+        })
+        .unwrap();
+
+    // The second frame is due further out than the clock reaches.
+    assert_eq!(sent, 1);
+    assert_eq!(altitudes, [0]);
+    stop.join().unwrap();
+}
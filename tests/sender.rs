@@ -0,0 +1,145 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::transport::Transport;
+use pfly_rust::{wire, OverflowPolicy, PflyConnection, PflyError, SenderHandle};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+use common::frame;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reports each frame and then blocks until the test lets it through.
+#[derive(Debug)]
+struct Gated {
+    sent: mpsc::Sender<i32>,
+    release: mpsc::Receiver<()>,
+}
+
+impl Transport for Gated {
+    fn send(&mut self, payload: &[u8]) -> pfly_rust::Result<()> {
+        let (frame, _) = wire::decode(payload)?;
+        let _ = self.sent.send(frame.altitude);
+        self.release.recv().map_err(|_| PflyError::Disconnected)
+    }
+
+    fn close(&mut self) -> pfly_rust::Result<()> {
+        Ok(())
+    }
+}
+
+/// Spawns a sender whose thread is stuck sending frame 0, then offers frames 1 to 5.
+fn overflow(policy: OverflowPolicy) -> (Vec<i32>, u64) {
+    let (sent, sent_rx) = mpsc::channel();
+    let (release, release_rx) = mpsc::channel();
+    let connection = PflyConnection::with_transport(Gated {
+        sent,
+        release: release_rx,
+    });
+    let sender = connection.spawn_with(2, policy);
+
+    sender.send(frame(0)).unwrap();
+    assert_eq!(sent_rx.recv_timeout(TIMEOUT), Ok(0));
+    for altitude in 1..=5 {
+        sender.send(frame(altitude)).unwrap();
+    }
+
+    for _ in 0..3 {
+        release.send(()).unwrap();
+    }
+    sender.shutdown().unwrap();
+
+    (sent_rx.try_iter().collect(), sender.dropped())
+}
+
+#[test]
+fn handle_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync + Clone>() {}
+    assert_send_sync::<SenderHandle>();
+}
+
+#[test]
+fn sends_from_several_threads() {
+    let server = MockServer::start().unwrap();
+    let sender = server
+        .connection_builder()
+        .connect()
+        .unwrap()
+        .spawn_with(1000, OverflowPolicy::DropNewest);
+
+    let threads: Vec<_> = (0..4)
+        .map(|thread| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for altitude in 0..25 {
+                    sender.send(frame(thread * 100 + altitude)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    sender.shutdown().unwrap();
+
+    let mut received: Vec<_> = server.iter().take(100).map(|data| data.altitude).collect();
+    received.sort_unstable();
+    assert_eq!(received.len(), 100);
+    assert_eq!(received[99], 324);
+    assert_eq!(sender.dropped(), 0);
+}
+
+#[test]
+fn drop_oldest_keeps_the_newest_frames() {
+    assert_eq!(overflow(OverflowPolicy::DropOldest), (vec![4, 5], 3));
+}
+
+#[test]
+fn drop_newest_keeps_the_queued_frames() {
+    assert_eq!(overflow(OverflowPolicy::DropNewest), (vec![1, 2], 3));
+}
+
+#[test]
+fn shutdown_flushes_the_final_frame() {
+    let server = MockServer::start().unwrap();
+    let sender = server
+        .connection_builder()
+        .max_rate(0.5)
+        .connect()
+        .unwrap()
+        .spawn();
+
+    for altitude in 0..5 {
+        sender.send(frame(altitude)).unwrap();
+    }
+    sender.shutdown().unwrap();
+
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 0);
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 4);
+    assert!(!sender.is_running());
+    assert!(matches!(
+        sender.send(frame(5)),
+        Err(PflyError::Disconnected)
+    ));
+}
+
+#[test]
+fn stops_on_errors() {
+    let (sent, _sent_rx) = mpsc::channel();
+    // Hanging up right away makes the first send fail.
+    let (_, release_rx) = mpsc::channel();
+    let sender = PflyConnection::with_transport(Gated {
+        sent,
+        release: release_rx,
+    })
+    .spawn();
+
+    sender.send(frame(0)).unwrap();
+    assert!(matches!(sender.shutdown(), Err(PflyError::Disconnected)));
+    assert!(!sender.is_running());
+    assert!(sender.send(frame(1)).is_err());
+    // The error was handed out already.
+    assert!(sender.shutdown().is_ok());
+}
@@ -0,0 +1,119 @@
+use pfly_rust::simulate::Flight;
+use pfly_rust::units::{Angle, Length, Speed, VerticalSpeed};
+use pfly_rust::PflyIpcData;
+
+fn toronto_montreal() -> Flight {
+    Flight::new(
+        (Angle::Degrees(43.6772), Angle::Degrees(-79.6306)),
+        (Angle::Degrees(45.4706), Angle::Degrees(-73.7408)),
+    )
+    .cruise_altitude(Length::Feet(24000.0))
+    .cruise_speed(Speed::Knots(420.0))
+    .aircraft_type("A320")
+}
+
+fn close_to(frame: &PflyIpcData, latitude: f64, longitude: f64) -> bool {
+    (frame.latitude - latitude).abs() < 0.05 && (frame.longitude - longitude).abs() < 0.05
+}
+
+#[test]
+fn flies_from_gate_to_gate() {
+    let frames: Vec<_> = toronto_montreal().frames().collect();
+    let first = frames.first().unwrap();
+    let last = frames.last().unwrap();
+
+    assert!(first.isOnGround && first.groundspeed == 0);
+    assert!(close_to(first, 43.6772, -79.6306));
+    assert!(last.isOnGround && last.groundspeed == 0);
+    assert!(close_to(last, 45.4706, -73.7408));
+
+    // Takes off once and lands once.
+    let changes = frames
+        .windows(2)
+        .filter(|pair| pair[0].isOnGround != pair[1].isOnGround)
+        .count();
+    assert_eq!(changes, 2);
+
+    assert!(frames.iter().any(|frame| frame.altitude == 24000));
+    assert!(frames.iter().all(|frame| frame.validate().is_ok()));
+    assert!(frames.iter().all(|frame| frame.aircraftType == "A320"));
+    assert!(frames.windows(2).all(|pair| pair[1].fuel <= pair[0].fuel));
+
+    // Roughly an hour, 280 miles at up to 420 knots plus taxiing.
+    assert!(
+        frames.len() > 2400 && frames.len() < 4800,
+        "{}",
+        frames.len()
+    );
+}
+
+#[test]
+fn touches_down_as_configured() {
+    let frames: Vec<_> = toronto_montreal()
+        .elevations(Length::Feet(569.0), Length::Feet(118.0))
+        .landing_vertical_speed(VerticalSpeed::FeetPerMinute(450.0))
+        .touchdown_g_force(1.6)
+        .frames()
+        .collect();
+
+    let touchdown = frames
+        .windows(2)
+        .position(|pair| !pair[0].isOnGround && pair[1].isOnGround)
+        .unwrap()
+        + 1;
+
+    assert!(frames[..touchdown]
+        .iter()
+        .all(|frame| frame.landingVerticalSpeed == 0));
+    assert_eq!(frames[touchdown].verticalSpeed, -450);
+    assert_eq!(frames[touchdown].landingVerticalSpeed, -450);
+    assert_eq!(frames[touchdown].gForce, 1600);
+    assert_eq!(frames[touchdown].altitude, 118);
+    assert!(frames[touchdown + 1].gForce < 1100);
+    assert!(frames[touchdown..]
+        .iter()
+        .all(|frame| frame.landingVerticalSpeed == -450 && frame.isOnGround));
+
+    // Never below the field on the way there.
+    assert!(frames[..touchdown]
+        .iter()
+        .all(|frame| frame.altitude >= 118));
+}
+
+#[test]
+fn same_seed_same_flight() {
+    let flight = toronto_montreal().seed(42);
+
+    assert!(flight.frames().eq(flight.frames()));
+    assert!(!flight.frames().eq(flight.clone().seed(43).frames()));
+}
+
+#[test]
+fn short_hop_never_reaches_cruise() {
+    let frames: Vec<_> = Flight::new(
+        (Angle::Degrees(43.6772), Angle::Degrees(-79.6306)),
+        (Angle::Degrees(43.6275), Angle::Degrees(-79.3962)),
+    )
+    .cruise_speed(Speed::Knots(110.0))
+    .frames()
+    .collect();
+
+    let highest = frames.iter().map(|frame| frame.altitude).max().unwrap();
+    assert!(highest > 1000 && highest < 35000, "{}", highest);
+    assert!(frames.last().unwrap().isOnGround);
+}
+
+#[test]
+fn zero_touchdown_rate_still_lands() {
+    let flight = toronto_montreal().landing_vertical_speed(VerticalSpeed::FeetPerMinute(0.0));
+    let usual = toronto_montreal().frames().count();
+
+    // Bounded, so a flight that never lands fails instead of hanging.
+    let frames: Vec<_> = flight.frames().take(usual * 2).collect();
+    assert!(frames.len() < usual * 2);
+
+    let touchdown = frames
+        .iter()
+        .find(|frame| frame.isOnGround && frame.landingVerticalSpeed != 0);
+    assert_eq!(touchdown.unwrap().landingVerticalSpeed, -1);
+}
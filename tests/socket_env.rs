@@ -0,0 +1,31 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::{default_socket_path, DEFAULT_SOCKET_PATH, SOCKET_PATH_ENV};
+use std::path::Path;
+use std::time::Duration;
+
+mod common;
+
+use common::frame;
+
+// Changes `PFLY_SOCKET`, which is process wide, so this is the only test in its binary.
+#[test]
+fn init_uses_socket_from_environment() {
+    let server = MockServer::start().unwrap();
+    std::env::set_var(SOCKET_PATH_ENV, server.path());
+    assert_eq!(default_socket_path(), server.path());
+
+    let socket = pfly_rust::init().unwrap();
+    pfly_rust::send_message(&socket, &frame(569)).unwrap();
+
+    let received = server.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(received.altitude, 569);
+    assert_eq!(received.latitude, 43.6772222);
+    assert_eq!(received.aircraftType, "B77W");
+
+    // Empty counts as not set.
+    std::env::set_var(SOCKET_PATH_ENV, "");
+    assert_eq!(default_socket_path(), Path::new(DEFAULT_SOCKET_PATH));
+
+    std::env::remove_var(SOCKET_PATH_ENV);
+    assert_eq!(default_socket_path(), Path::new(DEFAULT_SOCKET_PATH));
+}
@@ -0,0 +1,102 @@
+use pfly_rust::mock::MockServer;
+use pfly_rust::transport::{TcpTransport, Transport};
+use pfly_rust::{PflyConnection, PflyError, PflyIpcData, ReconnectingConnection};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod common;
+
+use common::frame;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+#[test]
+fn sends_frames_over_tcp() {
+    let server = MockServer::start_tcp().unwrap();
+    let builder = server.connection_builder();
+    assert!(builder.tcp_address().is_some());
+
+    let mut connection = builder.connect().unwrap();
+    assert!(connection.socket().is_none());
+
+    for altitude in 0..50 {
+        connection.send(&frame(altitude)).unwrap();
+    }
+    connection.close().unwrap();
+
+    let received: Vec<_> = server.iter().take(50).collect();
+    assert_eq!(received[0], frame(0));
+    assert_eq!(received[49].altitude, 49);
+    assert_eq!(server.connections(), 1);
+}
+
+#[test]
+fn tcp_connection_refused() {
+    // Grab a free port and let go of it again, nothing listens there afterwards.
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let result = TcpTransport::connect(("127.0.0.1", port), Some(TIMEOUT));
+    assert!(matches!(result, Err(PflyError::ConnectionRefused(_))));
+
+    let result = PflyConnection::builder().tcp("not an address").connect();
+    assert!(matches!(result, Err(PflyError::Address(_))));
+}
+
+#[test]
+fn reconnects_over_tcp() {
+    let server = MockServer::start_tcp().unwrap();
+    let mut connection = ReconnectingConnection::new(server.connection_builder());
+
+    connection.send(&frame(1)).unwrap();
+    assert_eq!(server.recv_timeout(TIMEOUT).unwrap().altitude, 1);
+
+    server.disconnect_all();
+    // The first write after the hang up may still succeed, the next ones can't.
+    let lost = (2..20).any(|altitude| {
+        std::thread::sleep(Duration::from_millis(10));
+        connection.send(&frame(altitude)).is_err()
+    });
+    assert!(lost);
+}
+
+/// Keeps everything in memory, to show any byte stream can carry frames.
+#[derive(Debug, Clone, Default)]
+struct Memory(Arc<Mutex<Vec<u8>>>);
+
+impl Transport for Memory {
+    fn send(&mut self, payload: &[u8]) -> pfly_rust::Result<()> {
+        self.0.lock().unwrap().extend_from_slice(payload);
+        Ok(())
+    }
+
+    fn close(&mut self) -> pfly_rust::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_transport() {
+    let memory = Memory::default();
+    let mut connection = PflyConnection::builder()
+        .strict(true)
+        .with_transport(memory.clone());
+
+    connection.send(&frame(1200)).unwrap();
+    let invalid = PflyIpcData {
+        latitude: f64::NAN,
+        ..frame(0)
+    };
+    assert!(matches!(
+        connection.send(&invalid),
+        Err(PflyError::Invalid(_))
+    ));
+
+    let bytes = memory.0.lock().unwrap().clone();
+    assert_eq!(bytes, pfly_rust::wire::encode(&frame(1200)));
+    assert!(connection.into_socket().is_none());
+}
@@ -0,0 +1,70 @@
+use pfly_rust::units::{
+    Angle, Length, Speed, VerticalSpeed, FEET_PER_METER, KNOTS_PER_METER_PER_SECOND,
+    METERS_PER_FOOT, METERS_PER_SECOND_PER_KNOT,
+};
+use std::f64::consts::PI;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn lengths() {
+    assert!(close(Length::Feet(1.0).meters(), 0.3048));
+    assert!(close(Length::Meters(0.3048).feet(), 1.0));
+    assert!(close(Length::Meters(1000.0).feet(), 3280.839895013123));
+    assert_eq!(Length::Feet(569.0).feet(), 569.0);
+    assert_eq!(Length::Meters(173.4).meters(), 173.4);
+}
+
+#[test]
+fn speeds() {
+    assert!(close(
+        Speed::Knots(1.0).meters_per_second(),
+        1852.0 / 3600.0
+    ));
+    assert!(close(Speed::MetersPerSecond(1852.0 / 3600.0).knots(), 1.0));
+    assert!(close(Speed::KilometersPerHour(1.852).knots(), 1.0));
+    assert!(close(
+        Speed::KilometersPerHour(3.6).meters_per_second(),
+        1.0
+    ));
+    assert_eq!(Speed::Knots(151.0).knots(), 151.0);
+}
+
+#[test]
+fn vertical_speeds() {
+    assert!(close(
+        VerticalSpeed::MetersPerSecond(5.08).feet_per_minute(),
+        1000.0
+    ));
+    assert!(close(
+        VerticalSpeed::FeetPerMinute(-1000.0).meters_per_second(),
+        -5.08
+    ));
+    assert_eq!(
+        VerticalSpeed::FeetPerMinute(-712.0).feet_per_minute(),
+        -712.0
+    );
+    assert_eq!(
+        VerticalSpeed::MetersPerSecond(-3.5).meters_per_second(),
+        -3.5
+    );
+}
+
+#[test]
+fn angles() {
+    assert!(close(Angle::Radians(PI).degrees(), 180.0));
+    assert!(close(Angle::Degrees(-90.0).radians(), -PI / 2.0));
+    assert_eq!(Angle::Degrees(43.6772222).degrees(), 43.6772222);
+    assert_eq!(Angle::Radians(1.0).radians(), 1.0);
+}
+
+#[test]
+fn constants_are_inverses() {
+    assert!(close(FEET_PER_METER * METERS_PER_FOOT, 1.0));
+    assert!(close(
+        KNOTS_PER_METER_PER_SECOND * METERS_PER_SECOND_PER_KNOT,
+        1.0
+    ));
+}
@@ -0,0 +1,103 @@
+use pfly_rust::{PflyIpcData, Violation};
+
+mod common;
+
+use common::frame;
+
+#[test]
+fn a_parked_aircraft_is_valid() {
+    assert_eq!(frame(569).validate(), Ok(()));
+    assert_eq!(PflyIpcData::builder().build().validate(), Ok(()));
+}
+
+#[test]
+fn collects_every_violation() {
+    let garbage = PflyIpcData {
+        latitude: 91.0,
+        longitude: f64::INFINITY,
+        altitude: 200_000,
+        ias: -1,
+        pitch: 95,
+        headingTrue: 360,
+        headingMagnetic: -1,
+        transponder: 7778,
+        ..frame(569)
+    };
+
+    assert_eq!(
+        garbage.validate().unwrap_err(),
+        vec![
+            Violation::OutOfRange {
+                field: "latitude",
+                value: 91.0,
+                min: -90.0,
+                max: 90.0
+            },
+            Violation::NotFinite { field: "longitude" },
+            Violation::OutOfRange {
+                field: "altitude",
+                value: 200_000.0,
+                min: -2_000.0,
+                max: 100_000.0
+            },
+            Violation::OutOfRange {
+                field: "ias",
+                value: -1.0,
+                min: 0.0,
+                max: 2_000.0
+            },
+            Violation::OutOfRange {
+                field: "pitch",
+                value: 95.0,
+                min: -90.0,
+                max: 90.0
+            },
+            Violation::Heading {
+                field: "headingTrue",
+                value: 360
+            },
+            Violation::Heading {
+                field: "headingMagnetic",
+                value: -1
+            },
+            Violation::NonOctalSquawk(7778),
+        ]
+    );
+}
+
+#[test]
+fn limits_are_inclusive() {
+    let edge = PflyIpcData {
+        latitude: -90.0,
+        longitude: 180.0,
+        altitude: -2_000,
+        pitch: -90,
+        roll: 180,
+        headingTrue: 359,
+        headingMagnetic: 0,
+        ..frame(0)
+    };
+
+    assert_eq!(edge.validate(), Ok(()));
+}
+
+#[test]
+fn squawks_are_four_octal_digits() {
+    let squawk = |transponder| {
+        PflyIpcData {
+            transponder,
+            ..frame(0)
+        }
+        .validate()
+        .is_ok()
+    };
+
+    assert!(squawk(0));
+    assert!(squawk(7));
+    assert!(squawk(1200));
+    assert!(squawk(7777));
+    assert!(!squawk(8));
+    assert!(!squawk(1908));
+    assert!(!squawk(10000));
+    assert!(!squawk(-1));
+}
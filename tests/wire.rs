@@ -0,0 +1,174 @@
+use pfly_rust::simulate::Flight;
+use pfly_rust::units::{Angle, Length};
+use pfly_rust::{wire, BridgeType, PflyError, PflyIpcData};
+use std::io::{self, Cursor};
+
+fn frame() -> PflyIpcData {
+    PflyIpcData {
+        altitude: 569,
+        agl: 0,
+        groundspeed: 0,
+        ias: 0,
+        headingTrue: 0,
+        headingMagnetic: 0,
+        latitude: 43.6772222,
+        longitude: -79.6305556,
+        verticalSpeed: 0,
+        landingVerticalSpeed: 0,
+        gForce: 1000,
+        fuel: 20000,
+        transponder: 1425,
+        bridgeType: BridgeType::XPlane,
+        isOnGround: true,
+        isSlew: false,
+        isPaused: false,
+        pitch: 0,
+        roll: 0,
+        time: 0,
+        fps: 120,
+        aircraftType: "B77W".into(),
+    }
+}
+
+#[rustfmt::skip]
+const GOLDEN: [u8; 92] = [
+    0x39, 0x02, 0x00, 0x00, // altitude 569
+    0x00, 0x00, 0x00, 0x00, // agl
+    0x00, 0x00, 0x00, 0x00, // groundspeed
+    0x00, 0x00, 0x00, 0x00, // ias
+    0x00, 0x00, 0x00, 0x00, // headingTrue
+    0x00, 0x00, 0x00, 0x00, // headingMagnetic
+    0x06, 0x90, 0x90, 0x37, 0xaf, 0xd6, 0x45, 0x40, // latitude 43.6772222
+    0xd1, 0x13, 0xe0, 0x05, 0x5b, 0xe8, 0x53, 0xc0, // longitude -79.6305556
+    0x00, 0x00, 0x00, 0x00, // verticalSpeed
+    0x00, 0x00, 0x00, 0x00, // landingVerticalSpeed
+    0xe8, 0x03, 0x00, 0x00, // gForce 1000
+    0x20, 0x4e, 0x00, 0x00, // fuel 20000
+    0x91, 0x05, 0x00, 0x00, // transponder 1425
+    0x03, // bridgeType xplane
+    0x01, 0x00, 0x00, // isOnGround, isSlew, isPaused
+    0x00, 0x00, 0x00, 0x00, // pitch
+    0x00, 0x00, 0x00, 0x00, // roll
+    0x00, 0x00, 0x00, 0x00, // time
+    0x78, 0x00, 0x00, 0x00, // fps 120
+    0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // aircraftType length
+    b'B', b'7', b'7', b'W',
+];
+
+#[test]
+fn encodes_known_frame() {
+    assert_eq!(wire::encode(&frame()), GOLDEN);
+    assert_eq!(wire::encoded_len(&frame()), GOLDEN.len());
+
+    let empty = PflyIpcData::default();
+    let mut bytes = vec![0xff];
+    wire::encode_into(&empty, &mut bytes);
+    assert_eq!(bytes.len(), 1 + wire::FIXED_LEN + 8);
+    assert_eq!(bytes[1 + 60], 3);
+    assert!(bytes[1 + wire::FIXED_LEN..].iter().all(|byte| *byte == 0));
+}
+
+#[test]
+fn matches_bincode() {
+    let flight = Flight::new(
+        (Angle::Degrees(43.6772), Angle::Degrees(-79.6306)),
+        (Angle::Degrees(45.4706), Angle::Degrees(-73.7408)),
+    )
+    .cruise_altitude(Length::Feet(24000.0))
+    .aircraft_type("A320 ✈")
+    .seed(7);
+
+    let frames = std::iter::once(frame())
+        .chain(std::iter::once(PflyIpcData::default()))
+        .chain(flight.frames().step_by(50));
+
+    for frame in frames {
+        let bytes = wire::encode(&frame);
+        assert_eq!(bytes, bincode::serialize(&frame).unwrap());
+        assert_eq!(wire::decode(&bytes).unwrap(), (frame, bytes.len()));
+    }
+}
+
+#[test]
+fn decodes_back_to_back_frames() {
+    let mut bytes = GOLDEN.to_vec();
+    let second = PflyIpcData {
+        isPaused: true,
+        aircraftType: "".into(),
+        ..frame()
+    };
+    wire::encode_into(&second, &mut bytes);
+
+    assert_eq!(wire::frame_len(&bytes[..87]), None);
+    assert_eq!(wire::frame_len(&bytes[..88]), Some(GOLDEN.len() as u64));
+
+    let (first, len) = wire::decode(&bytes).unwrap();
+    assert_eq!(first, frame());
+    assert_eq!(wire::decode(&bytes[len..]).unwrap().0, second);
+
+    let mut stream = Cursor::new(bytes);
+    assert_eq!(wire::read_frame(&mut stream).unwrap(), frame());
+    assert_eq!(wire::read_frame(&mut stream).unwrap(), second);
+    let end = wire::read_frame(&mut stream).unwrap_err();
+    assert_eq!(end.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn rejects_malformed_frames() {
+    let malformed = |bytes: &[u8]| matches!(wire::decode(bytes), Err(PflyError::MalformedFrame(_)));
+
+    assert!(malformed(&GOLDEN[..50]));
+    assert!(malformed(&GOLDEN[..GOLDEN.len() - 1]));
+
+    let mut flag = GOLDEN;
+    flag[61] = 2;
+    assert!(malformed(&flag));
+
+    let mut utf8 = GOLDEN;
+    utf8[88] = 0xff;
+    assert!(malformed(&utf8));
+
+    let mut length = GOLDEN;
+    length[87] = 0xff;
+    assert!(malformed(&length));
+    let err = wire::read_frame(&length[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn unknown_bridge_types_pass_through() {
+    let mut bridge = GOLDEN;
+    bridge[60] = 9;
+
+    let (frame, _) = wire::decode(&bridge).unwrap();
+    assert_eq!(frame.bridgeType, BridgeType::Other(9));
+    assert_eq!(wire::encode(&frame), bridge);
+}
+
+#[test]
+fn read_frame_caps_the_aircraft_type() {
+    let with_type = |len: usize| PflyIpcData {
+        aircraftType: "A".repeat(len).into(),
+        ..frame()
+    };
+
+    let longest = with_type(wire::MAX_AIRCRAFT_TYPE_LEN);
+    let bytes = wire::encode(&longest);
+    assert_eq!(wire::read_frame(&bytes[..]).unwrap(), longest);
+
+    // `decode` works on bytes that are already there and has no cap.
+    let bytes = wire::encode(&with_type(wire::MAX_AIRCRAFT_TYPE_LEN + 1));
+    assert!(wire::decode(&bytes).is_ok());
+    let err = wire::read_frame(&bytes[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("aircraft type is too long"));
+}
+
+#[test]
+fn read_frame_stops_at_a_truncated_frame() {
+    let err = wire::read_frame(&GOLDEN[..GOLDEN.len() - 1]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let err = wire::read_frame(&GOLDEN[..40]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
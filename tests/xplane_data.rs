@@ -0,0 +1,88 @@
+use pfly_rust::sources::xplane::data::{DataListener, DataMapping};
+use pfly_rust::sources::Source;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+fn packet(records: &[(i32, [f32; 8])]) -> Vec<u8> {
+    let mut packet = b"DATA*".to_vec();
+    for (index, values) in records {
+        packet.extend_from_slice(&index.to_le_bytes());
+        for value in values {
+            packet.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    packet
+}
+
+const UNUSED: f32 = -999.0;
+
+#[test]
+fn emits_frame_once_all_data_sets_arrived() {
+    let mut listener = DataListener::bind("127.0.0.1:0", DataMapping::default())
+        .unwrap()
+        .aircraft_type("C172");
+    listener
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let xplane = UdpSocket::bind("127.0.0.1:0").unwrap();
+    xplane
+        .send_to(
+            &packet(&[
+                (0, [49.6, 0.02, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+                (3, [121.2, 120.0, 125.1, 118.6, UNUSED, 139.5, 144.0, 136.5]),
+                (4, [0.19, UNUSED, -642.0, UNUSED, 1.08, 0.02, 0.0, UNUSED]),
+            ]),
+            addr,
+        )
+        .unwrap();
+    xplane
+        .send_to(
+            &packet(&[
+                (17, [3.1, -2.4, 359.8, 5.4, UNUSED, UNUSED, UNUSED, UNUSED]),
+                (20, [43.677, -79.63, 1569.0, 1000.0, 0.0, 1570.0, 0.0, 0.0]),
+                (63, [2000.0, 400.0, 300.0, 0.0, 2700.0, 0.0, 0.0, 0.0]),
+            ]),
+            addr,
+        )
+        .unwrap();
+
+    let frame = listener.recv_frame().unwrap();
+
+    assert_eq!(frame.fps, 50);
+    assert_eq!(frame.ias, 121);
+    assert_eq!(frame.groundspeed, 119);
+    assert_eq!(frame.verticalSpeed, -642);
+    assert_eq!(frame.gForce, 1080);
+    assert_eq!(frame.pitch, 3);
+    assert_eq!(frame.roll, -2);
+    assert_eq!(frame.headingTrue, 0);
+    assert_eq!(frame.headingMagnetic, 5);
+    assert!((frame.latitude - 43.677).abs() < 1e-4);
+    assert!((frame.longitude + 79.63).abs() < 1e-4);
+    assert_eq!(frame.altitude, 1569);
+    assert_eq!(frame.agl, 1000);
+    assert!(!frame.isOnGround);
+    assert_eq!(frame.fuel, 136);
+    assert_eq!(frame.aircraftType, "C172");
+}
+
+#[test]
+fn waits_for_required_indices() {
+    let mut listener = DataListener::bind(
+        "127.0.0.1:0",
+        DataMapping::empty().set(pfly_rust::sources::Field::Ias, 3, 0, 1.0),
+    )
+    .unwrap();
+
+    listener.update(&packet(&[(4, [0.0; 8])])).unwrap();
+    assert!(listener.frame().is_none());
+
+    listener
+        .update(&packet(&[(3, [250.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])]))
+        .unwrap();
+    assert_eq!(listener.frame().unwrap().ias, 250);
+
+    assert!(listener.update(b"DATA*\x03\x00").is_err());
+}
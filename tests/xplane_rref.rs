@@ -0,0 +1,164 @@
+use pfly_rust::sources::xplane::RrefSource;
+use pfly_rust::sources::Source;
+use pfly_rust::BridgeType;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+/// Pretends to be X-Plane: collects subscriptions and answers them with `values` by dataref path.
+fn fake_xplane(
+    values: HashMap<&'static str, f32>,
+) -> (u16, thread::JoinHandle<Vec<(i32, String)>>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let port = socket.local_addr().unwrap().port();
+
+    let handle = thread::spawn(move || {
+        let mut subscriptions = Vec::new();
+        let mut buffer = [0u8; 1024];
+
+        while let Ok((len, from)) = socket.recv_from(&mut buffer) {
+            let packet = &buffer[..len];
+            assert_eq!(len, 413);
+            assert_eq!(&packet[..5], b"RREF\0");
+
+            let frequency = i32::from_le_bytes(packet[5..9].try_into().unwrap());
+            let index = i32::from_le_bytes(packet[9..13].try_into().unwrap());
+            let path = String::from_utf8(packet[13..].to_vec()).unwrap();
+            let path = path.trim_end_matches('\0').to_owned();
+
+            if frequency == 0 {
+                break;
+            }
+            subscriptions.push((index, path));
+
+            if subscriptions.len() == values.len() {
+                let mut response = b"RREF,".to_vec();
+                for (index, path) in &subscriptions {
+                    response.extend_from_slice(&index.to_le_bytes());
+                    response.extend_from_slice(&values[path.as_str()].to_le_bytes());
+                }
+                socket.send_to(&response, from).unwrap();
+            }
+        }
+
+        subscriptions
+    });
+
+    (port, handle)
+}
+
+#[test]
+fn maps_datarefs_onto_frame() {
+    let values: HashMap<&'static str, f32> = vec![
+        ("sim/flightmodel/position/elevation", 173.4),
+        ("sim/flightmodel/position/y_agl", 3.048),
+        ("sim/flightmodel/position/groundspeed", 77.2),
+        ("sim/cockpit2/gauges/indicators/airspeed_kts_pilot", 151.4),
+        ("sim/flightmodel/position/true_psi", 359.7),
+        ("sim/flightmodel/position/mag_psi", 10.2),
+        ("sim/flightmodel/position/latitude", 43.677_223),
+        ("sim/flightmodel/position/longitude", -79.630_554),
+        ("sim/flightmodel/position/vh_ind_fpm", -712.0),
+        ("sim/flightmodel/forces/g_nrml", 1.234),
+        ("sim/flightmodel/weight/m_fuel_total", 20000.0),
+        ("sim/cockpit/radios/transponder_code", 1425.0),
+        ("sim/flightmodel/failures/onground_any", 1.0),
+        ("sim/operation/override/override_planepath[0]", 0.0),
+        ("sim/time/paused", 0.0),
+        ("sim/flightmodel/position/theta", 2.6),
+        ("sim/flightmodel/position/phi", -1.2),
+        ("sim/operation/misc/frame_rate_period", 0.02),
+        ("sim/aircraft/view/acf_ICAO[0]", f32::from(b'B')),
+        ("sim/aircraft/view/acf_ICAO[1]", f32::from(b'7')),
+        ("sim/aircraft/view/acf_ICAO[2]", f32::from(b'7')),
+        ("sim/aircraft/view/acf_ICAO[3]", f32::from(b'W')),
+    ]
+    .into_iter()
+    .collect();
+    let dataref_count = values.len();
+    let (port, xplane) = fake_xplane(values);
+
+    let mut source = RrefSource::connect(("127.0.0.1", port), 20).unwrap();
+    source
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let frame = source.recv_frame().unwrap();
+
+    assert_eq!(frame.altitude, 569);
+    assert_eq!(frame.agl, 10);
+    assert_eq!(frame.groundspeed, 150);
+    assert_eq!(frame.ias, 151);
+    assert_eq!(frame.headingTrue, 0);
+    assert_eq!(frame.headingMagnetic, 10);
+    assert!((frame.latitude - 43.6772222).abs() < 1e-4);
+    assert!((frame.longitude + 79.6305556).abs() < 1e-4);
+    assert_eq!(frame.verticalSpeed, -712);
+    assert_eq!(frame.gForce, 1234);
+    assert_eq!(frame.fuel, 20000);
+    assert_eq!(frame.transponder, 1425);
+    assert!(frame.isOnGround);
+    assert!(!frame.isSlew);
+    assert!(!frame.isPaused);
+    assert_eq!(frame.pitch, 3);
+    assert_eq!(frame.roll, -1);
+    assert_eq!(frame.fps, 50);
+    assert_eq!(frame.bridgeType, BridgeType::XPlane);
+    assert_eq!(frame.aircraftType, "B77W");
+
+    // Dropping the source unsubscribes, which ends the fake X-Plane.
+    drop(source);
+    let subscriptions = xplane.join().unwrap();
+    assert_eq!(subscriptions.len(), dataref_count);
+}
+
+/// Reads requests as `(frequency, index)` until none arrive for a while, and who sent them.
+fn requests(xplane: &UdpSocket) -> (Vec<(i32, i32)>, Option<SocketAddr>) {
+    let mut buffer = [0u8; 1024];
+    let mut requests = Vec::new();
+    let mut source = None;
+
+    while let Ok((len, from)) = xplane.recv_from(&mut buffer) {
+        assert_eq!(len, 413);
+        let frequency = i32::from_le_bytes(buffer[5..9].try_into().unwrap());
+        let index = i32::from_le_bytes(buffer[9..13].try_into().unwrap());
+        requests.push((frequency, index));
+        source = Some(from);
+    }
+
+    (requests, source)
+}
+
+#[test]
+fn resubscribing_starts_over() {
+    let xplane = UdpSocket::bind("127.0.0.1:0").unwrap();
+    xplane
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    let port = xplane.local_addr().unwrap().port();
+
+    let mut source = RrefSource::connect(("127.0.0.1", port), 20).unwrap();
+    source
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let (subscribed, from) = requests(&xplane);
+    assert!(!subscribed.is_empty());
+    assert!(subscribed.iter().all(|(frequency, _)| *frequency == 20));
+
+    let mut response = b"RREF,".to_vec();
+    for (_, index) in &subscribed {
+        response.extend_from_slice(&index.to_le_bytes());
+        response.extend_from_slice(&0f32.to_le_bytes());
+    }
+    xplane.send_to(&response, from.unwrap()).unwrap();
+    assert!(source.recv_frame().is_ok());
+
+    // As if X-Plane restarted: the old values are gone and the subscriptions go out again.
+    source.reset().unwrap();
+    assert!(source.frame().is_none());
+    assert_eq!(requests(&xplane).0, subscribed);
+}